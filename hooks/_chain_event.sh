@@ -0,0 +1,32 @@
+#!/bin/bash
+#
+# > make a file executable
+# chmod +x ./_chain_event.sh
+#
+# > positional arguments:
+# 1st - Event name (e.g. Balances.Transfer)
+# 2nd - Block number
+# 3rd - Event fields as JSON (e.g. {"from":"5GTD..","to":"5FHn..","amount":1000000000000})
+#
+# The following arguments depend on exposed flags
+# 4th - Network name (--expose-network flag must be set)
+# 5th - Network token symbol (--expose-network flag must be set)
+# 6th - Network token decimals (--expose-network flag must be set)
+#
+# > Special character '!' controls message visibility on Matrix (Element)
+# Any message that starts with '!' will be sent to Matrix, to the user private room
+# 
+# echo "! This message will be sent to Matrix"
+# echo "This message will NOT be sent to Matrix"
+# 
+# ***** START *****
+#
+echo "! e.g. Write your own script here"
+echo "! --------------------------------"
+echo "! Positional arguments:"
+echo "! 1st - Event name -> $1"
+echo "! 2nd - Block number -> $2"
+echo "! 3rd - Event fields -> $3"
+echo "! -------------------------------"
+#
+# ***** END *****
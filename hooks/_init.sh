@@ -37,11 +37,26 @@
 # 24th - Last era validator points (--expose-era-points flag must be set)
 # 25th - Last era validators average points (--expose-era-points flag must be set)
 #
+# > JSON payload (--enable-hook-payload flag must be set)
+# The same data is written to stdin as a versioned JSON document and exported
+# as named environment variables (e.g. SCOUTY_STASH, SCOUTY_NETWORK_NAME)
+#
+# payload=$(cat)
+# echo "! $(echo $payload | jq -r '.data.identity')"
+#
 # > Special character '!' controls message visibility on Matrix (Element)
 # Any message that starts with '!' will be sent to Matrix, to the user private room
 # 
 # echo "! This message will be sent to Matrix"
 # echo "This message will NOT be sent to Matrix"
+#
+# > Severity, metrics and directives
+# echo "!warn This message will be sent to Matrix as a warning"
+# echo "!! This message will be sent to Matrix as critical"
+# echo "#metric free_disk_gb 42.5"
+# echo "#severity warning"
+# echo "#suppress" (the notification is not sent if every hook asks for it)
+# exit 2 (raises the notification to critical without being a failure)
 # 
 # ***** START *****
 #
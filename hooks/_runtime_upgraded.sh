@@ -0,0 +1,34 @@
+#!/bin/bash
+#
+# > make a file executable
+# chmod +x ./_runtime_upgraded.sh
+#
+# > positional arguments:
+# 1st - Previous runtime spec version
+# 2nd - New runtime spec version
+# 3rd - Block number
+# 4th - Compatible (true if the storage entries and events used by scouty are unchanged)
+#
+# The following arguments depend on exposed flags
+# 5th - Network name (--expose-network flag must be set)
+# 6th - Network token symbol (--expose-network flag must be set)
+# 7th - Network token decimals (--expose-network flag must be set)
+#
+# > Special character '!' controls message visibility on Matrix (Element)
+# Any message that starts with '!' will be sent to Matrix, to the user private room
+# 
+# echo "! This message will be sent to Matrix"
+# echo "This message will NOT be sent to Matrix"
+# 
+# ***** START *****
+#
+echo "! e.g. Write your own script here"
+echo "! --------------------------------"
+echo "! Positional arguments:"
+echo "! 1st - Previous runtime spec version -> $1" 
+echo "! 2nd - New runtime spec version -> $2"
+echo "! 3rd - Block number -> $3"
+echo "! 4th - Compatible -> $4"
+echo "! -------------------------------"
+#
+# ***** END *****
@@ -0,0 +1,66 @@
+#!/bin/bash
+#
+# > make a file executable
+# chmod +x ./_scheduled.sh
+#
+# > executed every --interval (in seconds) or on --hook-scheduled-cron
+#
+# > positional arguments:
+# 1st - Stash
+# 2nd - Identity
+# 3rd - Queued session keys (0x..)
+# 4th - Is active? (true/false)
+# 5th - Session keys queued? (true/false)
+# 6th - Era
+# 7th - Session
+# 8th - Eras session index [1,2,3,4,5,6]
+# 9th - Block number
+#
+# The following arguments depend on exposed flags
+# 10th - Network name (--expose-network flag must be set)
+# 11th - Network token symbol (--expose-network flag must be set)
+# 12th - Network token decimals (--expose-network flag must be set)
+#
+# 13th - Projected APR (--expose-nominators flag must be set)
+# 14th - Validator Total stake (--expose-nominators flag must be set)
+# 15th - Validator Own stake (--expose-nominators flag must be set)
+# 16th - Active Nominators stashes [stash_1, stash_2, ..] (--expose-nominators flag must be set)
+# 17th - Active Nominators stakes [stake_1, stake_2, ..] (--expose-nominators flag must be set)
+#
+# 18th - Number of Authored blocks in previous Session (--expose-authored-blocks flag must be set)
+# 19th - Number of Authored blocks in previous 6 Sessions (--expose-authored-blocks flag must be set)
+#
+# 20th - All Nominators stashes [stash_1, stash_2, ..] (--expose-all-nominators flag must be set)
+# 21st - Total Nominators stake and raw distribution stake per nominee (--expose-all-nominators flag must be set)
+#
+# 22nd - Is Para validator? (true/false) (--expose-para-validator flag must be set)
+# 23rd - Number of times para validator in the previous 6 sessions (--expose-para-validator flag must be set)
+#
+# 24th - Last era validator points (--expose-era-points flag must be set)
+# 25th - Last era validators average points (--expose-era-points flag must be set)
+#
+# > Special character '!' controls message visibility on Matrix (Element)
+# Any message that starts with '!' will be sent to Matrix, to the user private room
+# 
+# echo "! This message will be sent to Matrix"
+# echo "This message will NOT be sent to Matrix"
+# 
+# ***** START *****
+#
+echo "! e.g. Write your own script here"
+echo "! --------------------------------"
+echo "! Positional arguments:"
+echo "! 1st - Stash -> $1"
+echo "! 4th - Is active? -> $4"
+echo "! 9th - Block number -> $9"
+echo "! -------------------------------"
+#
+# System metrics, e.g. every 5 minutes with --hook-scheduled-cron "*/5 * * * *"
+# USERNAME="USERNAME"
+# e.g the IP address could be stored in a file and be loaded based depending on the validator stash
+# IPADDRESS_FILENAME="$(dirname $0)/node/stashes/$1"
+# IPADDRESS=$( <$IPADDRESS_FILENAME )
+# VERIFY_SYSTEM_METRICS="$( $(dirname $0)/node/verify_system_metrics.sh $USERNAME $IPADDRESS )"
+# echo "$VERIFY_SYSTEM_METRICS"
+#
+# ***** END *****
@@ -15,6 +15,9 @@
 # 7th - Network token symbol (--expose-network flag must be set)
 # 8th - Network token decimals (--expose-network flag must be set)
 #
+# 9th - Offline signals observed in the previous session, comma separated
+#       (disabled, no_bitfields, no_authored_blocks)
+#
 # > Special character '!' controls message visibility on Matrix (Element)
 # Any message that starts with '!' will be sent to Matrix, to the user private room
 # 
@@ -31,6 +34,7 @@ echo "! 2nd - Identity -> $2"
 echo "! 3rd - Queued session keys -> ${3:0:16}.."
 echo "! 4th - Is active? -> $4"
 echo "! 5th - Session keys queued? -> $5"
+echo "! 9th - Offline signals -> $9"
 echo "! -------------------------------"
 #
 # ***** END *****
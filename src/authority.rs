@@ -53,6 +53,10 @@ impl AuthorityRecords {
         self.current_session_index = new_session_index;
     }
 
+    pub fn current_session_index(&self) -> u32 {
+        self.current_session_index
+    }
+
     pub fn set_authorities(&mut self, new_authorities: Vec<AccountId32>) {
         self.authorities = new_authorities;
     }
@@ -130,7 +134,7 @@ impl AuthorityRecords {
                 None => 0,
             };
         }
-        return total;
+        total
     }
 
     fn remove(&mut self, stash: &AccountId32) {
@@ -30,12 +30,18 @@
 //
 // Set Config struct into a CONFIG lazy_static to avoid multiple processing.
 //
-use clap::{App, Arg};
-use dotenv;
+use crate::email::{parse_mailboxes, SmtpSecurity};
+use crate::event_hooks::EventHook;
+use crate::hooks::{HOOK_KEYS, HOOK_TEST_KEYS};
+use crate::incidents::Condition;
+use crate::schedule::{Cron, Schedule};
+use crate::slack::SlackWebhook;
+use clap::{App, Arg, SubCommand};
 use lazy_static::lazy_static;
 use log::info;
 use serde::Deserialize;
-use std::env;
+use std::{collections::BTreeMap, env, fs, str::FromStr, time::Duration};
+use subxt::utils::AccountId32;
 
 // Set Config struct into a CONFIG lazy_static to avoid multiple processing
 lazy_static! {
@@ -52,15 +58,78 @@ fn default_error_interval() -> u64 {
     30
 }
 
+/// provides default value for catch up max blocks if SCOUTY_CATCH_UP_MAX_BLOCKS env var is not set
+fn default_catch_up_max_blocks() -> u32 {
+    256
+}
+
+/// provides default value for hook timeout if SCOUTY_HOOK_TIMEOUT env var is not set
+fn default_hook_timeout() -> u64 {
+    60
+}
+
+/// provides default value for hook http retries if SCOUTY_HOOK_HTTP_RETRIES env var is not set
+fn default_hook_http_retries() -> u32 {
+    2
+}
+
+/// provides default value for hook env allowlist if SCOUTY_HOOK_ENV_ALLOWLIST env var is not set
+fn default_hook_env_allowlist() -> Vec<String> {
+    [
+        "PATH", "HOME", "USER", "LOGNAME", "SHELL", "LANG", "LC_*", "TZ", "TMPDIR",
+    ]
+    .iter()
+    .map(|name| name.to_string())
+    .collect()
+}
+
+/// provides default value for hook max stdout bytes if SCOUTY_HOOK_MAX_STDOUT_BYTES env var is not set
+fn default_hook_max_stdout_bytes() -> usize {
+    1048576
+}
+
+/// provides default value for email subject if SCOUTY_EMAIL_SUBJECT env var is not set
+fn default_email_subject() -> String {
+    "[scouty] {chain} {section} report (era {era})".to_string()
+}
+
+/// provides default value for opsgenie api url if SCOUTY_OPSGENIE_API_URL env var is not set
+fn default_opsgenie_api_url() -> String {
+    "https://api.opsgenie.com".to_string()
+}
+
+/// provides default value for incident conditions if SCOUTY_INCIDENT_CONDITIONS env var is not set
+fn default_incident_conditions() -> Vec<Condition> {
+    Condition::all()
+}
+
+/// provides default value for hook concurrency if SCOUTY_HOOK_CONCURRENCY env var is not set
+fn default_hook_concurrency() -> usize {
+    4
+}
+
 #[derive(Clone, Deserialize, Debug)]
 pub struct Config {
     #[serde(default = "default_interval")]
     pub interval: u64,
     #[serde(default = "default_error_interval")]
     pub error_interval: u64,
+    #[serde(default = "default_catch_up_max_blocks")]
+    pub catch_up_max_blocks: u32,
     pub substrate_ws_url: String,
+    #[serde(default)]
     pub stashes: Vec<String>,
     #[serde(default)]
+    pub stashes_config_path: String,
+    // NOTE: loaded from the stashes config file, not from env vars
+    #[serde(skip)]
+    pub stash_configs: Vec<StashConfig>,
+    #[serde(default)]
+    pub event_hooks_path: String,
+    // NOTE: loaded from the event hooks config file, not from env vars
+    #[serde(skip)]
+    pub event_hooks: Vec<EventHook>,
+    #[serde(default)]
     pub is_debug: bool,
     #[serde(default)]
     pub is_short: bool,
@@ -83,6 +152,45 @@ pub struct Config {
     pub hook_validator_offline_path: String,
     #[serde(default)]
     pub hook_referenda_submitted_path: String,
+    #[serde(default)]
+    pub hook_runtime_upgraded_path: String,
+    #[serde(default)]
+    pub hook_scheduled_path: String,
+    #[serde(default)]
+    pub hook_scheduled_cron: String,
+    #[serde(default)]
+    pub hook_payload_enabled: bool,
+    // NOTE: a timeout of 0 means no limit
+    #[serde(default = "default_hook_timeout")]
+    pub hook_timeout: u64,
+    // NOTE: timeouts of particular hooks by hook name, e.g. from SCOUTY_HOOK_NEW_ERA_TIMEOUT
+    #[serde(skip)]
+    pub hook_timeouts: BTreeMap<String, u64>,
+    #[serde(default = "default_hook_concurrency")]
+    pub hook_concurrency: usize,
+    #[serde(default)]
+    pub hook_retries: u32,
+    #[serde(default)]
+    pub hook_http_secret: String,
+    #[serde(default)]
+    pub hook_http_headers: Vec<String>,
+    #[serde(default = "default_hook_http_retries")]
+    pub hook_http_retries: u32,
+    // hooks sandbox
+    #[serde(default = "default_hook_env_allowlist")]
+    pub hook_env_allowlist: Vec<String>,
+    #[serde(default)]
+    pub hook_working_dir: String,
+    #[serde(default)]
+    pub hook_uid: Option<u32>,
+    #[serde(default)]
+    pub hook_gid: Option<u32>,
+    #[serde(default)]
+    pub hook_max_cpu_seconds: Option<u64>,
+    #[serde(default)]
+    pub hook_max_memory_mb: Option<u64>,
+    #[serde(default = "default_hook_max_stdout_bytes")]
+    pub hook_max_stdout_bytes: usize,
     // matrix configuration
     #[serde(default)]
     pub matrix_user: String,
@@ -94,6 +202,62 @@ pub struct Config {
     pub matrix_disabled: bool,
     #[serde(default)]
     pub matrix_bot_display_name_disabled: bool,
+    // telegram configuration
+    #[serde(default)]
+    pub telegram_bot_token: String,
+    #[serde(default)]
+    pub telegram_chat_ids: Vec<String>,
+    #[serde(default)]
+    pub telegram_disabled: bool,
+    // discord configuration
+    #[serde(default)]
+    pub discord_webhook_url: String,
+    #[serde(default)]
+    pub discord_bot_token: String,
+    #[serde(default)]
+    pub discord_channel_id: String,
+    #[serde(default)]
+    pub discord_disabled: bool,
+    // slack configuration
+    #[serde(default)]
+    pub slack_webhooks_path: String,
+    #[serde(skip)]
+    pub slack_webhooks: Vec<SlackWebhook>,
+    #[serde(default)]
+    pub slack_disabled: bool,
+    // email configuration
+    #[serde(default)]
+    pub smtp_host: String,
+    // NOTE: 0 means the default port of the smtp security mode
+    #[serde(default)]
+    pub smtp_port: u16,
+    #[serde(default)]
+    pub smtp_security: SmtpSecurity,
+    #[serde(default)]
+    pub smtp_username: String,
+    #[serde(default)]
+    pub smtp_password: String,
+    #[serde(default)]
+    pub email_from: String,
+    #[serde(default)]
+    pub email_to: Vec<String>,
+    #[serde(default)]
+    pub email_cc: Vec<String>,
+    #[serde(default = "default_email_subject")]
+    pub email_subject: String,
+    #[serde(default)]
+    pub email_disabled: bool,
+    // incidents configuration
+    #[serde(default)]
+    pub pagerduty_routing_key: String,
+    #[serde(default)]
+    pub opsgenie_api_key: String,
+    #[serde(default = "default_opsgenie_api_url")]
+    pub opsgenie_api_url: String,
+    #[serde(default = "default_incident_conditions")]
+    pub incident_conditions: Vec<Condition>,
+    #[serde(default)]
+    pub incidents_disabled: bool,
     // chain settings exposure
     #[serde(default)]
     pub expose_network: bool,
@@ -109,6 +273,186 @@ pub struct Config {
     pub expose_era_points: bool,
     #[serde(default)]
     pub expose_all: bool,
+    // replay mode
+    #[serde(default)]
+    pub replay_from: Option<u32>,
+    #[serde(default)]
+    pub replay_to: Option<u32>,
+    // hooks test and describe commands
+    #[serde(default)]
+    pub hooks_test: Option<String>,
+    #[serde(default)]
+    pub hooks_test_stash: String,
+    #[serde(default)]
+    pub hooks_test_at: Option<u32>,
+    #[serde(default)]
+    pub hooks_test_path: String,
+    #[serde(default)]
+    pub hooks_describe: Option<String>,
+}
+
+/// Hook paths that can be set for a particular validator stash
+#[derive(Clone, Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct StashHooks {
+    pub init: Option<String>,
+    pub new_session: Option<String>,
+    pub new_era: Option<String>,
+    pub validator_starts_active_next_era: Option<String>,
+    pub validator_starts_inactive_next_era: Option<String>,
+    pub validator_chilled: Option<String>,
+    pub validator_slashed: Option<String>,
+    pub validator_offline: Option<String>,
+    pub scheduled: Option<String>,
+}
+
+/// Expose flags that can be set for a particular validator stash
+#[derive(Clone, Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct StashExpose {
+    pub network: Option<bool>,
+    pub nominators: Option<bool>,
+    pub authored_blocks: Option<bool>,
+    pub all_nominators: Option<bool>,
+    pub para_validator: Option<bool>,
+    pub era_points: Option<bool>,
+    pub all: Option<bool>,
+}
+
+/// Settings of a particular validator stash, anything not set falls back to the global settings
+#[derive(Clone, Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct StashConfig {
+    pub stash: String,
+    #[serde(default)]
+    pub label: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub hooks: StashHooks,
+    #[serde(default)]
+    pub expose: StashExpose,
+    // NOTE: notifications of the stash are sent to a private room with this matrix user
+    #[serde(default)]
+    pub matrix_user: String,
+}
+
+impl Config {
+    /// Returns the maximum time a hook is allowed to run, if any, a timeout of 0 means no limit
+    pub fn timeout_for_hook(&self, name: &str) -> Option<Duration> {
+        let timeout = self
+            .hook_timeouts
+            .get(name)
+            .copied()
+            .unwrap_or(self.hook_timeout);
+        (timeout > 0).then(|| Duration::from_secs(timeout))
+    }
+
+    /// Returns the settings defined for a particular validator stash, if any
+    pub fn stash_config(&self, stash: &AccountId32) -> Option<&StashConfig> {
+        self.stash_configs
+            .iter()
+            .find(|c| AccountId32::from_str(&c.stash).ok().as_ref() == Some(stash))
+    }
+
+    /// Returns the config with the settings of the validator stash applied on top of the global ones
+    pub fn for_stash(&self, stash: &AccountId32) -> Config {
+        let mut config = self.clone();
+        if let Some(c) = self.stash_config(stash) {
+            let hooks = c.hooks.clone();
+            let paths = [
+                (hooks.init, &mut config.hook_init_path),
+                (hooks.new_session, &mut config.hook_new_session_path),
+                (hooks.new_era, &mut config.hook_new_era_path),
+                (
+                    hooks.validator_starts_active_next_era,
+                    &mut config.hook_validator_starts_active_next_era_path,
+                ),
+                (
+                    hooks.validator_starts_inactive_next_era,
+                    &mut config.hook_validator_starts_inactive_next_era_path,
+                ),
+                (
+                    hooks.validator_chilled,
+                    &mut config.hook_validator_chilled_path,
+                ),
+                (
+                    hooks.validator_slashed,
+                    &mut config.hook_validator_slashed_path,
+                ),
+                (
+                    hooks.validator_offline,
+                    &mut config.hook_validator_offline_path,
+                ),
+                (hooks.scheduled, &mut config.hook_scheduled_path),
+            ];
+            for (path, global) in paths {
+                if let Some(path) = path {
+                    *global = path;
+                }
+            }
+
+            let expose = &c.expose;
+            let flags = [
+                (expose.network, &mut config.expose_network),
+                (expose.nominators, &mut config.expose_nominators),
+                (expose.authored_blocks, &mut config.expose_authored_blocks),
+                (expose.all_nominators, &mut config.expose_all_nominators),
+                (expose.para_validator, &mut config.expose_para_validator),
+                (expose.era_points, &mut config.expose_era_points),
+                (expose.all, &mut config.expose_all),
+            ];
+            for (flag, global) in flags {
+                if let Some(flag) = flag {
+                    *global = flag;
+                }
+            }
+
+            if !c.matrix_user.is_empty() {
+                config.matrix_user = c.matrix_user.clone();
+            }
+        }
+        config
+    }
+
+    /// Returns the schedule of the scheduled hook, the cron expression takes precedence over the interval
+    pub fn schedule(&self) -> Schedule {
+        match self.hook_scheduled_cron.parse::<Cron>() {
+            Ok(cron) if !self.hook_scheduled_cron.is_empty() => Schedule::Cron(cron),
+            _ => Schedule::Interval(self.interval),
+        }
+    }
+
+    /// Returns true if the predicate holds for the settings of any validator stash
+    pub fn any_stash(&self, predicate: impl Fn(&Config) -> bool) -> bool {
+        self.stashes
+            .iter()
+            .filter_map(|stash| AccountId32::from_str(stash).ok())
+            .any(|stash| predicate(&self.for_stash(&stash)))
+    }
+
+    /// Returns the matrix users, other than the global one, to which stash notifications are sent
+    pub fn stash_matrix_users(&self) -> Vec<String> {
+        let mut users: Vec<String> = Vec::new();
+        for c in self.stash_configs.iter() {
+            if !c.matrix_user.is_empty()
+                && c.matrix_user != self.matrix_user
+                && !users.contains(&c.matrix_user)
+            {
+                users.push(c.matrix_user.clone());
+            }
+        }
+        users
+    }
+
+    /// Returns the ordered list of substrate websocket endpoints
+    pub fn substrate_ws_urls(&self) -> Vec<String> {
+        self.substrate_ws_url
+            .split(',')
+            .map(|url| url.trim().to_string())
+            .filter(|url| !url.is_empty())
+            .collect()
+    }
 }
 
 /// Inject dotenv and env vars into the Config struct
@@ -159,6 +503,123 @@ fn get_config() -> Config {
           "Disable matrix bot display name update for 'scouty'. (e.g. with this flag active 'scouty' will not change the matrix bot user display name)",
         ),
       )
+    .arg(
+      Arg::with_name("telegram-bot-token")
+        .long("telegram-bot-token")
+        .takes_value(true)
+        .help("Token of the 'Scouty Bot' Telegram bot, as given by @BotFather. If set, notifications are also sent to the Telegram chats defined by --telegram-chat-ids."))
+    .arg(
+      Arg::with_name("telegram-chat-ids")
+        .long("telegram-chat-ids")
+        .takes_value(true)
+        .help("Telegram chats to which the 'Scouty Bot' sends notifications. If needed specify more than one (e.g. 123456789,-1001234567890,@my_channel)."))
+    .arg(
+      Arg::with_name("disable-telegram")
+        .long("disable-telegram")
+        .help("Disable Telegram bot for 'scouty'. (e.g. with this flag active 'scouty' will not send messages/notifications to Telegram chats)"))
+    .arg(
+      Arg::with_name("discord-webhook-url")
+        .long("discord-webhook-url")
+        .takes_value(true)
+        .help("Discord webhook url to which notifications are sent. (e.g. https://discord.com/api/webhooks/<id>/<token>) Note: takes precedence over --discord-bot-token."))
+    .arg(
+      Arg::with_name("discord-bot-token")
+        .long("discord-bot-token")
+        .takes_value(true)
+        .help("Token of the 'Scouty Bot' Discord bot. If set, notifications are also sent to the Discord channel defined by --discord-channel-id."))
+    .arg(
+      Arg::with_name("discord-channel-id")
+        .long("discord-channel-id")
+        .takes_value(true)
+        .help("Discord channel to which the 'Scouty Bot' Discord bot sends notifications."))
+    .arg(
+      Arg::with_name("disable-discord")
+        .long("disable-discord")
+        .help("Disable Discord notifications for 'scouty'. (e.g. with this flag active 'scouty' will not send messages/notifications to Discord)"))
+    .arg(
+      Arg::with_name("slack-webhooks-path")
+        .long("slack-webhooks-path")
+        .takes_value(true)
+        .value_name("FILE")
+        .help("Sets the path of a JSON file with the Slack or Mattermost incoming webhooks to which notifications are sent, each one optionally restricted to a minimum severity and to particular validator stashes."))
+    .arg(
+      Arg::with_name("disable-slack")
+        .long("disable-slack")
+        .help("Disable Slack notifications for 'scouty'. (e.g. with this flag active 'scouty' will not send messages/notifications to Slack or Mattermost webhooks)"))
+    .arg(
+      Arg::with_name("smtp-host")
+        .long("smtp-host")
+        .takes_value(true)
+        .help("SMTP server through which reports are sent by email. (e.g. smtp.example.com)"))
+    .arg(
+      Arg::with_name("smtp-port")
+        .long("smtp-port")
+        .takes_value(true)
+        .help("SMTP server port. [default: 587 with starttls, 465 with tls and 25 with none]"))
+    .arg(
+      Arg::with_name("smtp-security")
+        .long("smtp-security")
+        .takes_value(true)
+        .possible_values(&["starttls", "tls", "none"])
+        .help("How the connection to the SMTP server is secured. Note: use none only with a local SMTP server or sink. [default: starttls]"))
+    .arg(
+      Arg::with_name("smtp-username")
+        .long("smtp-username")
+        .takes_value(true)
+        .help("Username for the SMTP server authentication."))
+    .arg(
+      Arg::with_name("smtp-password")
+        .long("smtp-password")
+        .takes_value(true)
+        .help("Password for the SMTP server authentication."))
+    .arg(
+      Arg::with_name("email-from")
+        .long("email-from")
+        .takes_value(true)
+        .help("Sender of the emails. (e.g. 'Scouty <scouty@example.com>')"))
+    .arg(
+      Arg::with_name("email-to")
+        .long("email-to")
+        .takes_value(true)
+        .help("Recipients of the emails. If needed specify more than one (e.g. ops@example.com,audit@example.com)."))
+    .arg(
+      Arg::with_name("email-cc")
+        .long("email-cc")
+        .takes_value(true)
+        .help("Recipients of the emails in copy. If needed specify more than one (e.g. cto@example.com,cfo@example.com)."))
+    .arg(
+      Arg::with_name("email-subject")
+        .long("email-subject")
+        .takes_value(true)
+        .help("Subject template of the emails, in which {chain}, {era}, {section} and {severity} are replaced by the values of each report. [default: '[scouty] {chain} {section} report (era {era})']"))
+    .arg(
+      Arg::with_name("disable-email")
+        .long("disable-email")
+        .help("Disable email notifications for 'scouty'. (e.g. with this flag active 'scouty' will not send reports by email)"))
+    .arg(
+      Arg::with_name("pagerduty-routing-key")
+        .long("pagerduty-routing-key")
+        .takes_value(true)
+        .help("Integration key of a PagerDuty service (Events API v2). If set, incidents are triggered and resolved in PagerDuty for the conditions defined by --incident-conditions."))
+    .arg(
+      Arg::with_name("opsgenie-api-key")
+        .long("opsgenie-api-key")
+        .takes_value(true)
+        .help("API key of an Opsgenie API integration. If set, alerts are created and closed in Opsgenie for the conditions defined by --incident-conditions."))
+    .arg(
+      Arg::with_name("opsgenie-api-url")
+        .long("opsgenie-api-url")
+        .takes_value(true)
+        .help("Opsgenie API url. (e.g. https://api.eu.opsgenie.com for accounts in the EU region) [default: https://api.opsgenie.com]"))
+    .arg(
+      Arg::with_name("incident-conditions")
+        .long("incident-conditions")
+        .takes_value(true)
+        .help("Validator conditions for which an incident is opened, and resolved once the condition clears. If needed specify more than one (e.g. slashed,chilled). [default: slashed,chilled,starts_inactive_next_era,offline]"))
+    .arg(
+      Arg::with_name("disable-incidents")
+        .long("disable-incidents")
+        .help("Disable incidents for 'scouty'. (e.g. with this flag active 'scouty' will not open or resolve incidents in PagerDuty or Opsgenie)"))
     .arg(
       Arg::with_name("short")
         .long("short")
@@ -169,6 +630,16 @@ fn get_config() -> Config {
         .takes_value(true)
         .default_value("30")
         .help("Interval value (in minutes) from which 'scouty' will restart again in case of a critical error."))
+    .arg(
+      Arg::with_name("interval")
+        .long("interval")
+        .takes_value(true)
+        .help("Interval value (in seconds) at which the scheduled hook is executed. [default: 21600]"))
+    .arg(
+      Arg::with_name("catch-up-max-blocks")
+        .long("catch-up-max-blocks")
+        .takes_value(true)
+        .help("Maximum number of finalized blocks that 'scouty' will catch up on after reconnecting. If more blocks were missed, 'scouty' skips them and starts again from the current head. (NOTE: pruned nodes only keep state for the last 256 blocks, increase it when connected to an archive node) [default: 256]"))
     .arg(
       Arg::with_name("stashes")
         .short("s")
@@ -178,13 +649,22 @@ fn get_config() -> Config {
           "Validator stash addresses for which 'scouty' will take a particular eye. If needed specify more than one (e.g. stash_1,stash_2,stash_3).",
         ),
     )
+    .arg(
+      Arg::with_name("stashes-config-path")
+        .long("stashes-config-path")
+        .takes_value(true)
+        .value_name("FILE")
+        .help(
+          "Sets the path of a JSON file with settings for particular validator stashes (e.g. label, tags, hook paths, expose flags and matrix user). Stashes defined in this file are added to the ones defined by --stashes. Anything not set for a stash falls back to the global settings.",
+        ),
+    )
     .arg(
       Arg::with_name("substrate-ws-url")
         .short("w")
         .long("substrate-ws-url")
         .takes_value(true)
         .help(
-          "Substrate websocket endpoint for which 'scouty' will try to connect. (e.g. wss://kusama-rpc.polkadot.io) (NOTE: substrate_ws_url takes precedence than <CHAIN> argument)",
+          "Substrate websocket endpoint for which 'scouty' will try to connect. (e.g. wss://kusama-rpc.polkadot.io) If needed specify more than one, ordered by preference, and 'scouty' will switch to the next healthy endpoint when the current one drops or lags (e.g. url_1,url_2). (NOTE: substrate_ws_url takes precedence than <CHAIN> argument)",
         ),
     )
     .arg(
@@ -247,6 +727,78 @@ fn get_config() -> Config {
           "Expose all positional arguments for some of the hooks. Note: Each hook bash script describes which data is available through the positional arguments.",
         ),
       )
+    .arg(
+      Arg::with_name("enable-hook-payload")
+        .long("enable-hook-payload")
+        .help(
+          "Write a versioned JSON document with all the data collected to the stdin of each hook script, and export the same fields as named `SCOUTY_*` environment variables. Note: positional arguments are still passed as before.",
+        ),
+      )
+    .arg(
+      Arg::with_name("hook-timeout")
+        .long("hook-timeout")
+        .takes_value(true)
+        .help("Maximum time (in seconds) that a hook script is allowed to run. Scripts running for longer are killed and reported as timed out. Note: the timeout of a particular hook can be set with SCOUTY_HOOK_<NAME>_TIMEOUT (e.g. SCOUTY_HOOK_NEW_ERA_TIMEOUT=600), 0 means no limit. [default: 60]"))
+    .arg(
+      Arg::with_name("hook-concurrency")
+        .long("hook-concurrency")
+        .takes_value(true)
+        .help("Maximum number of validator stashes for which hook scripts run at the same time. Note: hooks of the same stash always run in order. [default: 4]"))
+    .arg(
+      Arg::with_name("hook-retries")
+        .long("hook-retries")
+        .takes_value(true)
+        .help("Number of times a hook script that failed (non zero exit code) is retried, with exponential backoff. Note: failed hooks never stop 'scouty' from monitoring on-chain events. [default: 0]"))
+    .arg(
+      Arg::with_name("hook-http-secret")
+        .long("hook-http-secret")
+        .takes_value(true)
+        .help("Secret used to sign the JSON payload posted to HTTP(S) hooks. The HMAC-SHA256 signature is sent in the `X-Scouty-Signature` header (e.g. sha256=<hex>)."))
+    .arg(
+      Arg::with_name("hook-http-headers")
+        .long("hook-http-headers")
+        .takes_value(true)
+        .help("Additional headers sent to HTTP(S) hooks, e.g. for authentication. If needed specify more than one (e.g. \"Authorization: Bearer token,X-Api-Key: key\")."))
+    .arg(
+      Arg::with_name("hook-http-retries")
+        .long("hook-http-retries")
+        .takes_value(true)
+        .help("Number of times a request to an HTTP(S) hook is retried, with exponential backoff, if the endpoint is unreachable or responds with a server error. [default: 2]"))
+    .arg(
+      Arg::with_name("hook-env-allowlist")
+        .long("hook-env-allowlist")
+        .takes_value(true)
+        .help("Environment variables passed to hook scripts, any other variable is scrubbed. Names ending with '*' match any variable with that prefix. (NOTE: scouty secrets are never passed) [default: PATH,HOME,USER,LOGNAME,SHELL,LANG,LC_*,TZ,TMPDIR]"))
+    .arg(
+      Arg::with_name("hook-working-dir")
+        .long("hook-working-dir")
+        .takes_value(true)
+        .help("Working directory in which hook scripts run. [default: scouty working directory]"))
+    .arg(
+      Arg::with_name("hook-uid")
+        .long("hook-uid")
+        .takes_value(true)
+        .help("User id to which hook scripts drop privileges. (NOTE: scouty must run as root to change user)"))
+    .arg(
+      Arg::with_name("hook-gid")
+        .long("hook-gid")
+        .takes_value(true)
+        .help("Group id to which hook scripts drop privileges. (NOTE: scouty must run as root to change group)"))
+    .arg(
+      Arg::with_name("hook-max-cpu-seconds")
+        .long("hook-max-cpu-seconds")
+        .takes_value(true)
+        .help("Maximum CPU time (in seconds) that a hook script is allowed to use."))
+    .arg(
+      Arg::with_name("hook-max-memory-mb")
+        .long("hook-max-memory-mb")
+        .takes_value(true)
+        .help("Maximum memory (in MB of address space) that a hook script is allowed to use."))
+    .arg(
+      Arg::with_name("hook-max-stdout-bytes")
+        .long("hook-max-stdout-bytes")
+        .takes_value(true)
+        .help("Maximum number of bytes captured from a hook script stdout, the rest of the output is discarded. [default: 1048576]"))
     .arg(
         Arg::with_name("hook-init-path")
           .long("hook-init-path")
@@ -319,17 +871,148 @@ fn get_config() -> Config {
           "Sets the path for the script that is called every time one of the Validator stashes defined is offline at the end of a session.",
         ),
     )
+    .arg(
+      Arg::with_name("hook-runtime-upgraded-path")
+        .long("hook-runtime-upgraded-path")
+        .takes_value(true)
+        .value_name("FILE")
+        .help(
+          "Sets the path for the script that is called every time a runtime upgrade is enacted on the network.",
+        ),
+    )
+    .arg(
+      Arg::with_name("hook-scheduled-path")
+        .long("hook-scheduled-path")
+        .takes_value(true)
+        .value_name("FILE")
+        .help(
+          "Sets the path for the script that is called periodically, independent of chain events, every --interval or on --hook-scheduled-cron. It gets the same data as the new era hook.",
+        ),
+    )
+    .arg(
+      Arg::with_name("hook-scheduled-cron")
+        .long("hook-scheduled-cron")
+        .takes_value(true)
+        .help(
+          "Cron expression (minute hour day-of-month month day-of-week, in UTC) on which the scheduled hook is executed, instead of every --interval (e.g. \"*/5 * * * *\").",
+        ),
+    )
+    .arg(
+      Arg::with_name("event-hooks-path")
+        .long("event-hooks-path")
+        .takes_value(true)
+        .value_name("FILE")
+        .help(
+          "Sets the path of a JSON file with hooks called on any chain event matching a filter (e.g. `Balances.Transfer where from in stashes`). The event fields are passed to the script as JSON.",
+        ),
+    )
+    .subcommand(
+      SubCommand::with_name("replay")
+        .about("Replay finalized blocks within a range from an archive node through the same event handlers and hooks. Note: matrix, telegram, discord, slack and email messages are only sent, and incidents only opened, if `--enable-matrix`, `--enable-telegram`, `--enable-discord`, `--enable-slack`, `--enable-email` or `--enable-incidents` is set.")
+        .arg(
+          Arg::with_name("from")
+            .long("from")
+            .takes_value(true)
+            .required(true)
+            .value_name("BLOCK")
+            .help("Block number from which 'scouty' starts to replay blocks."),
+        )
+        .arg(
+          Arg::with_name("to")
+            .long("to")
+            .takes_value(true)
+            .required(true)
+            .value_name("BLOCK")
+            .help("Block number until which 'scouty' replays blocks (inclusive)."),
+        )
+        .arg(
+          Arg::with_name("enable-matrix")
+            .long("enable-matrix")
+            .help("Send matrix messages/notifications while replaying blocks."),
+        )
+        .arg(
+          Arg::with_name("enable-telegram")
+            .long("enable-telegram")
+            .help("Send Telegram messages/notifications while replaying blocks."),
+        )
+        .arg(
+          Arg::with_name("enable-discord")
+            .long("enable-discord")
+            .help("Send Discord messages/notifications while replaying blocks."),
+        )
+        .arg(
+          Arg::with_name("enable-slack")
+            .long("enable-slack")
+            .help("Send Slack messages/notifications while replaying blocks."),
+        )
+        .arg(
+          Arg::with_name("enable-email")
+            .long("enable-email")
+            .help("Send emails while replaying blocks."),
+        )
+        .arg(
+          Arg::with_name("enable-incidents")
+            .long("enable-incidents")
+            .help("Open and resolve incidents while replaying blocks."),
+        ),
+    )
+    .subcommand(
+      SubCommand::with_name("hooks")
+        .about("Test and describe hook scripts.")
+        .subcommand(
+          SubCommand::with_name("test")
+            .about("Run a hook once against live or historical chain data, with the same arguments the event handlers would produce, and print the script output and the report. Note: only the hook being tested runs and matrix messages are not sent.")
+            .arg(
+              Arg::with_name("HOOK")
+                .index(1)
+                .required(true)
+                .possible_values(&HOOK_TEST_KEYS)
+                .help("Hook to be tested."),
+            )
+            .arg(
+              Arg::with_name("stash")
+                .long("stash")
+                .takes_value(true)
+                .help("Validator stash for which the hook is tested. [default: all the stashes defined]"),
+            )
+            .arg(
+              Arg::with_name("at")
+                .long("at")
+                .takes_value(true)
+                .value_name("BLOCK")
+                .help("Block number at which chain data is read (NOTE: an archive node is required for old blocks). [default: latest finalized block]"),
+            )
+            .arg(
+              Arg::with_name("path")
+                .long("path")
+                .takes_value(true)
+                .value_name("FILE")
+                .help("Script, directory or HTTP(S) endpoint to be tested instead of the hook path configured."),
+            ),
+        )
+        .subcommand(
+          SubCommand::with_name("describe")
+            .about("List the positional arguments of a hook, with the respective payload field and meaning.")
+            .arg(
+              Arg::with_name("HOOK")
+                .index(1)
+                .required(true)
+                .possible_values(&HOOK_KEYS.map(|(key, _)| key))
+                .help("Hook to be described."),
+            ),
+        ),
+    )
     .get_matches();
 
     // Try to load configuration from file first
     let config_path = matches.value_of("config-path").unwrap_or(".env");
 
-    match dotenv::from_filename(&config_path).ok() {
-        Some(_) => info!("Loading configuration from {} file", &config_path),
-        None => {
+    match dotenv::from_filename(config_path) {
+        Ok(_) => info!("Loading configuration from {} file", &config_path),
+        Err(_) => {
             let config_path =
                 env::var("SCOUTY_CONFIG_FILENAME").unwrap_or(".env".to_string());
-            if let Some(_) = dotenv::from_filename(&config_path).ok() {
+            if dotenv::from_filename(&config_path).is_ok() {
                 info!("Loading configuration from {} file", &config_path);
             }
         }
@@ -362,6 +1045,10 @@ fn get_config() -> Config {
         env::set_var("SCOUTY_STASHES", stashes);
     }
 
+    if let Some(stashes_config_path) = matches.value_of("stashes-config-path") {
+        env::set_var("SCOUTY_STASHES_CONFIG_PATH", stashes_config_path);
+    }
+
     if let Some(substrate_ws_url) = matches.value_of("substrate-ws-url") {
         env::set_var("SCOUTY_SUBSTRATE_WS_URL", substrate_ws_url);
     }
@@ -440,6 +1127,27 @@ fn get_config() -> Config {
         );
     }
 
+    if let Some(hook_runtime_upgraded_path) =
+        matches.value_of("hook-runtime-upgraded-path")
+    {
+        env::set_var(
+            "SCOUTY_HOOK_RUNTIME_UPGRADED_PATH",
+            hook_runtime_upgraded_path,
+        );
+    }
+
+    if let Some(hook_scheduled_path) = matches.value_of("hook-scheduled-path") {
+        env::set_var("SCOUTY_HOOK_SCHEDULED_PATH", hook_scheduled_path);
+    }
+
+    if let Some(hook_scheduled_cron) = matches.value_of("hook-scheduled-cron") {
+        env::set_var("SCOUTY_HOOK_SCHEDULED_CRON", hook_scheduled_cron);
+    }
+
+    if let Some(event_hooks_path) = matches.value_of("event-hooks-path") {
+        env::set_var("SCOUTY_EVENT_HOOKS_PATH", event_hooks_path);
+    }
+
     if matches.is_present("expose-all") {
         env::set_var("SCOUTY_EXPOSE_ALL", "true");
     }
@@ -472,6 +1180,62 @@ fn get_config() -> Config {
         env::set_var("SCOUTY_EXPOSE_ALL", "true");
     }
 
+    if let Some(hook_timeout) = matches.value_of("hook-timeout") {
+        env::set_var("SCOUTY_HOOK_TIMEOUT", hook_timeout);
+    }
+
+    if let Some(hook_concurrency) = matches.value_of("hook-concurrency") {
+        env::set_var("SCOUTY_HOOK_CONCURRENCY", hook_concurrency);
+    }
+
+    if let Some(hook_retries) = matches.value_of("hook-retries") {
+        env::set_var("SCOUTY_HOOK_RETRIES", hook_retries);
+    }
+
+    if let Some(hook_http_secret) = matches.value_of("hook-http-secret") {
+        env::set_var("SCOUTY_HOOK_HTTP_SECRET", hook_http_secret);
+    }
+
+    if let Some(hook_http_headers) = matches.value_of("hook-http-headers") {
+        env::set_var("SCOUTY_HOOK_HTTP_HEADERS", hook_http_headers);
+    }
+
+    if let Some(hook_http_retries) = matches.value_of("hook-http-retries") {
+        env::set_var("SCOUTY_HOOK_HTTP_RETRIES", hook_http_retries);
+    }
+
+    if let Some(hook_env_allowlist) = matches.value_of("hook-env-allowlist") {
+        env::set_var("SCOUTY_HOOK_ENV_ALLOWLIST", hook_env_allowlist);
+    }
+
+    if let Some(hook_working_dir) = matches.value_of("hook-working-dir") {
+        env::set_var("SCOUTY_HOOK_WORKING_DIR", hook_working_dir);
+    }
+
+    if let Some(hook_uid) = matches.value_of("hook-uid") {
+        env::set_var("SCOUTY_HOOK_UID", hook_uid);
+    }
+
+    if let Some(hook_gid) = matches.value_of("hook-gid") {
+        env::set_var("SCOUTY_HOOK_GID", hook_gid);
+    }
+
+    if let Some(hook_max_cpu_seconds) = matches.value_of("hook-max-cpu-seconds") {
+        env::set_var("SCOUTY_HOOK_MAX_CPU_SECONDS", hook_max_cpu_seconds);
+    }
+
+    if let Some(hook_max_memory_mb) = matches.value_of("hook-max-memory-mb") {
+        env::set_var("SCOUTY_HOOK_MAX_MEMORY_MB", hook_max_memory_mb);
+    }
+
+    if let Some(hook_max_stdout_bytes) = matches.value_of("hook-max-stdout-bytes") {
+        env::set_var("SCOUTY_HOOK_MAX_STDOUT_BYTES", hook_max_stdout_bytes);
+    }
+
+    if matches.is_present("enable-hook-payload") {
+        env::set_var("SCOUTY_HOOK_PAYLOAD_ENABLED", "true");
+    }
+
     if matches.is_present("disable-matrix") {
         env::set_var("SCOUTY_MATRIX_DISABLED", "true");
     }
@@ -488,14 +1252,299 @@ fn get_config() -> Config {
         env::set_var("SCOUTY_MATRIX_BOT_PASSWORD", matrix_bot_password);
     }
 
+    if matches.is_present("disable-telegram") {
+        env::set_var("SCOUTY_TELEGRAM_DISABLED", "true");
+    }
+
+    if let Some(telegram_bot_token) = matches.value_of("telegram-bot-token") {
+        env::set_var("SCOUTY_TELEGRAM_BOT_TOKEN", telegram_bot_token);
+    }
+
+    if let Some(telegram_chat_ids) = matches.value_of("telegram-chat-ids") {
+        env::set_var("SCOUTY_TELEGRAM_CHAT_IDS", telegram_chat_ids);
+    }
+
+    if matches.is_present("disable-discord") {
+        env::set_var("SCOUTY_DISCORD_DISABLED", "true");
+    }
+
+    if let Some(discord_webhook_url) = matches.value_of("discord-webhook-url") {
+        env::set_var("SCOUTY_DISCORD_WEBHOOK_URL", discord_webhook_url);
+    }
+
+    if let Some(discord_bot_token) = matches.value_of("discord-bot-token") {
+        env::set_var("SCOUTY_DISCORD_BOT_TOKEN", discord_bot_token);
+    }
+
+    if let Some(discord_channel_id) = matches.value_of("discord-channel-id") {
+        env::set_var("SCOUTY_DISCORD_CHANNEL_ID", discord_channel_id);
+    }
+
+    if matches.is_present("disable-slack") {
+        env::set_var("SCOUTY_SLACK_DISABLED", "true");
+    }
+
+    if let Some(slack_webhooks_path) = matches.value_of("slack-webhooks-path") {
+        env::set_var("SCOUTY_SLACK_WEBHOOKS_PATH", slack_webhooks_path);
+    }
+
+    if matches.is_present("disable-email") {
+        env::set_var("SCOUTY_EMAIL_DISABLED", "true");
+    }
+
+    if let Some(smtp_host) = matches.value_of("smtp-host") {
+        env::set_var("SCOUTY_SMTP_HOST", smtp_host);
+    }
+
+    if let Some(smtp_port) = matches.value_of("smtp-port") {
+        env::set_var("SCOUTY_SMTP_PORT", smtp_port);
+    }
+
+    if let Some(smtp_security) = matches.value_of("smtp-security") {
+        env::set_var("SCOUTY_SMTP_SECURITY", smtp_security);
+    }
+
+    if let Some(smtp_username) = matches.value_of("smtp-username") {
+        env::set_var("SCOUTY_SMTP_USERNAME", smtp_username);
+    }
+
+    if let Some(smtp_password) = matches.value_of("smtp-password") {
+        env::set_var("SCOUTY_SMTP_PASSWORD", smtp_password);
+    }
+
+    if let Some(email_from) = matches.value_of("email-from") {
+        env::set_var("SCOUTY_EMAIL_FROM", email_from);
+    }
+
+    if let Some(email_to) = matches.value_of("email-to") {
+        env::set_var("SCOUTY_EMAIL_TO", email_to);
+    }
+
+    if let Some(email_cc) = matches.value_of("email-cc") {
+        env::set_var("SCOUTY_EMAIL_CC", email_cc);
+    }
+
+    if let Some(email_subject) = matches.value_of("email-subject") {
+        env::set_var("SCOUTY_EMAIL_SUBJECT", email_subject);
+    }
+
+    if matches.is_present("disable-incidents") {
+        env::set_var("SCOUTY_INCIDENTS_DISABLED", "true");
+    }
+
+    if let Some(pagerduty_routing_key) = matches.value_of("pagerduty-routing-key") {
+        env::set_var("SCOUTY_PAGERDUTY_ROUTING_KEY", pagerduty_routing_key);
+    }
+
+    if let Some(opsgenie_api_key) = matches.value_of("opsgenie-api-key") {
+        env::set_var("SCOUTY_OPSGENIE_API_KEY", opsgenie_api_key);
+    }
+
+    if let Some(opsgenie_api_url) = matches.value_of("opsgenie-api-url") {
+        env::set_var("SCOUTY_OPSGENIE_API_URL", opsgenie_api_url);
+    }
+
+    if let Some(incident_conditions) = matches.value_of("incident-conditions") {
+        env::set_var("SCOUTY_INCIDENT_CONDITIONS", incident_conditions);
+    }
+
     if let Some(error_interval) = matches.value_of("error-interval") {
         env::set_var("SCOUTY_ERROR_INTERVAL", error_interval);
     }
 
-    match envy::prefixed("SCOUTY_").from_env::<Config>() {
+    if let Some(interval) = matches.value_of("interval") {
+        env::set_var("SCOUTY_INTERVAL", interval);
+    }
+
+    if let Some(catch_up_max_blocks) = matches.value_of("catch-up-max-blocks") {
+        env::set_var("SCOUTY_CATCH_UP_MAX_BLOCKS", catch_up_max_blocks);
+    }
+
+    if let Some(replay_matches) = matches.subcommand_matches("replay") {
+        if let Some(from) = replay_matches.value_of("from") {
+            env::set_var("SCOUTY_REPLAY_FROM", from);
+        }
+
+        if let Some(to) = replay_matches.value_of("to") {
+            env::set_var("SCOUTY_REPLAY_TO", to);
+        }
+
+        if !replay_matches.is_present("enable-matrix") {
+            env::set_var("SCOUTY_MATRIX_DISABLED", "true");
+        }
+
+        if !replay_matches.is_present("enable-telegram") {
+            env::set_var("SCOUTY_TELEGRAM_DISABLED", "true");
+        }
+
+        if !replay_matches.is_present("enable-discord") {
+            env::set_var("SCOUTY_DISCORD_DISABLED", "true");
+        }
+
+        if !replay_matches.is_present("enable-slack") {
+            env::set_var("SCOUTY_SLACK_DISABLED", "true");
+        }
+
+        if !replay_matches.is_present("enable-email") {
+            env::set_var("SCOUTY_EMAIL_DISABLED", "true");
+        }
+
+        if !replay_matches.is_present("enable-incidents") {
+            env::set_var("SCOUTY_INCIDENTS_DISABLED", "true");
+        }
+    }
+
+    if let Some(hooks_matches) = matches.subcommand_matches("hooks") {
+        if let Some(test_matches) = hooks_matches.subcommand_matches("test") {
+            if let Some(hook) = test_matches.value_of("HOOK") {
+                env::set_var("SCOUTY_HOOKS_TEST", hook);
+            }
+
+            if let Some(stash) = test_matches.value_of("stash") {
+                env::set_var("SCOUTY_HOOKS_TEST_STASH", stash);
+            }
+
+            if let Some(at) = test_matches.value_of("at") {
+                env::set_var("SCOUTY_HOOKS_TEST_AT", at);
+            }
+
+            if let Some(path) = test_matches.value_of("path") {
+                env::set_var("SCOUTY_HOOKS_TEST_PATH", path);
+            }
+
+            env::set_var("SCOUTY_MATRIX_DISABLED", "true");
+            env::set_var("SCOUTY_TELEGRAM_DISABLED", "true");
+            env::set_var("SCOUTY_DISCORD_DISABLED", "true");
+            env::set_var("SCOUTY_SLACK_DISABLED", "true");
+            env::set_var("SCOUTY_EMAIL_DISABLED", "true");
+            env::set_var("SCOUTY_INCIDENTS_DISABLED", "true");
+        }
+
+        if let Some(describe_matches) = hooks_matches.subcommand_matches("describe") {
+            if let Some(hook) = describe_matches.value_of("HOOK") {
+                env::set_var("SCOUTY_HOOKS_DESCRIBE", hook);
+            }
+        }
+    }
+
+    let mut config = match envy::prefixed("SCOUTY_").from_env::<Config>() {
         Ok(config) => config,
         Err(error) => panic!("Configuration error: {:#?}", error),
+    };
+
+    if !config.stashes_config_path.is_empty() {
+        match load_stash_configs(&config.stashes_config_path) {
+            Ok(stash_configs) => {
+                for c in stash_configs.iter() {
+                    if !config.stashes.contains(&c.stash) {
+                        config.stashes.push(c.stash.clone());
+                    }
+                }
+                config.stash_configs = stash_configs;
+            }
+            Err(error) => panic!("Configuration error: {}", error),
+        }
+    }
+
+    match load_hook_timeouts(|var| env::var(var).ok()) {
+        Ok(hook_timeouts) => config.hook_timeouts = hook_timeouts,
+        Err(error) => panic!("Configuration error: {}", error),
+    }
+
+    if !config.hook_scheduled_cron.is_empty() {
+        if let Err(error) = config.hook_scheduled_cron.parse::<Cron>() {
+            panic!("Configuration error: {}", error);
+        }
+    }
+
+    if !config.event_hooks_path.is_empty() {
+        match load_event_hooks(&config.event_hooks_path) {
+            Ok(event_hooks) => config.event_hooks = event_hooks,
+            Err(error) => panic!("Configuration error: {}", error),
+        }
     }
+
+    if !config.slack_webhooks_path.is_empty() {
+        match load_slack_webhooks(&config.slack_webhooks_path) {
+            Ok(slack_webhooks) => config.slack_webhooks = slack_webhooks,
+            Err(error) => panic!("Configuration error: {}", error),
+        }
+    }
+
+    if !config.smtp_host.is_empty() && !config.email_disabled {
+        if config.email_to.is_empty() {
+            panic!("Configuration error: at least one email recipient must be defined");
+        }
+        let addresses = [
+            vec![config.email_from.clone()],
+            config.email_to.clone(),
+            config.email_cc.clone(),
+        ]
+        .concat();
+        if let Err(error) = parse_mailboxes(&addresses) {
+            panic!("Configuration error: {}", error);
+        }
+    }
+
+    // NOTE: a hook is only tested for the stash given, if any
+    if !config.hooks_test_stash.is_empty() {
+        config.stashes = vec![config.hooks_test_stash.clone()];
+    }
+
+    if config.stashes.is_empty() && config.hooks_describe.is_none() {
+        panic!("Configuration error: at least one validator stash must be defined");
+    }
+
+    config
+}
+
+/// Load the timeouts of particular hooks from SCOUTY_HOOK_<NAME>_TIMEOUT env vars
+fn load_hook_timeouts(
+    env_var: impl Fn(&str) -> Option<String>,
+) -> Result<BTreeMap<String, u64>, String> {
+    let mut hook_timeouts = BTreeMap::new();
+    for (key, name) in HOOK_KEYS.iter() {
+        let var = format!("SCOUTY_HOOK_{}_TIMEOUT", key.to_uppercase());
+        if let Some(value) = env_var(&var) {
+            let timeout = value
+                .parse::<u64>()
+                .map_err(|e| format!("{}: {}", var, e))?;
+            hook_timeouts.insert(name.to_string(), timeout);
+        }
+    }
+    Ok(hook_timeouts)
+}
+
+/// Load the settings of particular validator stashes from a JSON file
+fn load_stash_configs(path: &str) -> Result<Vec<StashConfig>, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    let stash_configs: Vec<StashConfig> =
+        serde_json::from_str(&content).map_err(|e| format!("{}: {}", path, e))?;
+    for c in stash_configs.iter() {
+        AccountId32::from_str(&c.stash)
+            .map_err(|e| format!("{}: invalid stash {}: {:?}", path, c.stash, e))?;
+    }
+    Ok(stash_configs)
+}
+
+/// Load the hooks called on chain events matching a filter from a JSON file
+fn load_event_hooks(path: &str) -> Result<Vec<EventHook>, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    serde_json::from_str(&content).map_err(|e| format!("{}: {}", path, e))
+}
+
+/// Load the Slack or Mattermost incoming webhooks from a JSON file
+fn load_slack_webhooks(path: &str) -> Result<Vec<SlackWebhook>, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    let slack_webhooks: Vec<SlackWebhook> =
+        serde_json::from_str(&content).map_err(|e| format!("{}: {}", path, e))?;
+    for webhook in slack_webhooks.iter() {
+        for stash in webhook.stashes.iter() {
+            AccountId32::from_str(stash)
+                .map_err(|e| format!("{}: invalid stash {}: {:?}", path, stash, e))?;
+        }
+    }
+    Ok(slack_webhooks)
 }
 
 #[cfg(test)]
@@ -508,9 +1557,82 @@ mod tests {
         assert_ne!(config.substrate_ws_url, "".to_string());
     }
 
+    #[test]
+    fn it_splits_substrate_ws_urls() {
+        let mut config = get_config();
+        config.substrate_ws_url =
+            "ws://127.0.0.1:9944, wss://rpc.polkadot.io:443,".into();
+        assert_eq!(
+            config.substrate_ws_urls(),
+            vec!["ws://127.0.0.1:9944", "wss://rpc.polkadot.io:443"]
+        );
+    }
+
+    #[test]
+    fn it_applies_stash_settings_on_top_of_global_ones() {
+        let mut config = get_config();
+        config.hook_new_session_path = "/opt/scouty/_new_session.sh".into();
+        config.hook_new_era_path = "/opt/scouty/_new_era.sh".into();
+        config.expose_nominators = true;
+        config.stash_configs = serde_json::from_str(
+            r#"[{
+                "stash": "5GTD7ZeD823BjpmZBCSzBQp7cvHR1Gunq7oDkurZr9zUev2n",
+                "label": "Customer A",
+                "hooks": { "new_session": "/opt/customer-a/_new_session.sh" },
+                "expose": { "nominators": false, "era_points": true },
+                "matrix_user": "@customer-a:matrix.org"
+            }]"#,
+        )
+        .unwrap();
+
+        let stash =
+            AccountId32::from_str("5GTD7ZeD823BjpmZBCSzBQp7cvHR1Gunq7oDkurZr9zUev2n")
+                .unwrap();
+        let c = config.for_stash(&stash);
+        assert_eq!(c.hook_new_session_path, "/opt/customer-a/_new_session.sh");
+        assert_eq!(c.hook_new_era_path, "/opt/scouty/_new_era.sh");
+        assert!(!c.expose_nominators);
+        assert!(c.expose_era_points);
+        assert_eq!(c.matrix_user, "@customer-a:matrix.org");
+
+        let other =
+            AccountId32::from_str("5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty")
+                .unwrap();
+        let c = config.for_stash(&other);
+        assert_eq!(c.hook_new_session_path, "/opt/scouty/_new_session.sh");
+        assert!(c.expose_nominators);
+    }
+
     #[test]
     fn it_gets_a_config_from_the_lazy_static() {
         let config = &CONFIG;
         assert_ne!(config.substrate_ws_url, "".to_string());
     }
+
+    #[test]
+    fn it_applies_per_hook_timeouts() {
+        use crate::hooks::{HOOK_INIT, HOOK_NEW_ERA, HOOK_NEW_SESSION};
+
+        let mut config = get_config();
+        config.hook_timeout = 0;
+        config.hook_timeouts = BTreeMap::new();
+        assert_eq!(config.timeout_for_hook(HOOK_NEW_SESSION), None);
+
+        let vars = BTreeMap::from([
+            ("SCOUTY_HOOK_NEW_ERA_TIMEOUT", "600"),
+            ("SCOUTY_HOOK_INIT_TIMEOUT", "0"),
+        ]);
+        config.hook_timeout = 60;
+        config.hook_timeouts =
+            load_hook_timeouts(|var| vars.get(var).map(|v| v.to_string())).unwrap();
+        assert_eq!(
+            config.timeout_for_hook(HOOK_NEW_SESSION),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(
+            config.timeout_for_hook(HOOK_NEW_ERA),
+            Some(Duration::from_secs(600))
+        );
+        assert_eq!(config.timeout_for_hook(HOOK_INIT), None);
+    }
 }
@@ -0,0 +1,451 @@
+// The MIT License (MIT)
+// Copyright © 2021 Aukbit Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::config::CONFIG;
+use crate::errors::{DiscordError, ScoutyError};
+use crate::hooks::Severity;
+use crate::notifier::{parse_html, split_message, unescape_text, Html, Notifier};
+use crate::report::{Card, Report, Status};
+use async_std::task;
+use async_trait::async_trait;
+use log::{debug, info, warn};
+use reqwest::header::HeaderMap;
+use serde::{Deserialize, Serialize};
+use std::{
+    result::Result,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+const DISCORD_API_URL: &str = "https://discord.com/api/v10";
+
+/// Discord limits, in characters unless stated otherwise
+const DISCORD_MAX_CONTENT_LENGTH: usize = 2000;
+const DISCORD_MAX_TITLE_LENGTH: usize = 256;
+const DISCORD_MAX_DESCRIPTION_LENGTH: usize = 4096;
+const DISCORD_MAX_FIELD_VALUE_LENGTH: usize = 1024;
+const DISCORD_MAX_FIELDS: usize = 25;
+const DISCORD_MAX_EMBEDS: usize = 10;
+const DISCORD_MAX_EMBEDS_LENGTH: usize = 6000;
+
+/// Maximum number of times a message is retried when rate limited
+const DISCORD_MAX_RETRIES: u32 = 3;
+
+#[derive(Serialize, Debug, Default)]
+struct AllowedMentions {
+    parse: Vec<String>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+struct EmbedField {
+    name: String,
+    value: String,
+    inline: bool,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+struct Embed {
+    title: String,
+    url: String,
+    color: u32,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    description: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    fields: Vec<EmbedField>,
+}
+
+impl Embed {
+    /// Returns the number of characters counted by Discord towards the embeds limit
+    fn len(&self) -> usize {
+        self.title.chars().count()
+            + self.description.chars().count()
+            + self
+                .fields
+                .iter()
+                .map(|f| f.name.chars().count() + f.value.chars().count())
+                .sum::<usize>()
+    }
+}
+
+impl From<&Card> for Embed {
+    /// Converts a validator card into an embed, with the hook alerts as fields
+    fn from(card: &Card) -> Embed {
+        let description = to_discord_markdown(&card.lines.join("<br/>"));
+        let fields = card
+            .alerts
+            .iter()
+            .take(DISCORD_MAX_FIELDS)
+            .map(|(severity, line)| EmbedField {
+                name: match severity {
+                    Severity::Critical => format!("🚨 {}", severity),
+                    _ => format!("⚠️ {}", severity),
+                },
+                value: truncate(
+                    &to_discord_markdown(line),
+                    DISCORD_MAX_FIELD_VALUE_LENGTH,
+                ),
+                inline: false,
+            })
+            .collect();
+        Embed {
+            title: truncate(&card.title, DISCORD_MAX_TITLE_LENGTH),
+            url: card.url.clone(),
+            color: status_color(card.status),
+            description: truncate(description.trim(), DISCORD_MAX_DESCRIPTION_LENGTH),
+            fields,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Default)]
+struct CreateMessage {
+    #[serde(skip_serializing_if = "String::is_empty")]
+    content: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    embeds: Vec<Embed>,
+    // NOTE: hook output must not be able to mention @everyone or any other user
+    allowed_mentions: AllowedMentions,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct RateLimitResponse {
+    #[serde(default)]
+    message: String,
+    retry_after: Option<f64>,
+}
+
+#[derive(Clone, Debug)]
+enum Destination {
+    Webhook(String),
+    Channel {
+        bot_token: String,
+        channel_id: String,
+    },
+}
+
+#[derive(Debug)]
+pub struct Discord {
+    client: reqwest::Client,
+    destination: Destination,
+    // NOTE: set when Discord reports that no requests are left until the bucket resets
+    rate_limited_until: Mutex<Option<Instant>>,
+}
+
+impl Discord {
+    /// Returns a Discord notifier if a webhook url, or a bot token and channel id, are defined
+    pub fn new() -> Option<Discord> {
+        let config = CONFIG.clone();
+        if config.discord_disabled {
+            return None;
+        }
+        let destination = if !config.discord_webhook_url.is_empty() {
+            info!("Messages will be sent to a Discord webhook");
+            Destination::Webhook(config.discord_webhook_url)
+        } else if !config.discord_bot_token.is_empty() {
+            if config.discord_channel_id.is_empty() {
+                warn!("Discord bot token defined without channel id, Discord messages will not be sent");
+                return None;
+            }
+            info!(
+                "Messages will be sent to Discord channel {}",
+                config.discord_channel_id
+            );
+            Destination::Channel {
+                bot_token: config.discord_bot_token,
+                channel_id: config.discord_channel_id,
+            }
+        } else {
+            return None;
+        };
+        Some(Discord {
+            client: reqwest::Client::new(),
+            destination,
+            rate_limited_until: Mutex::new(None),
+        })
+    }
+
+    /// Wait until the rate limit bucket resets, if no requests were left
+    async fn wait_rate_limit(&self) {
+        let wait = self
+            .rate_limited_until
+            .lock()
+            .unwrap()
+            .take()
+            .map(|until| until.saturating_duration_since(Instant::now()));
+        if let Some(wait) = wait {
+            debug!(
+                "Discord rate limit -> Wait {:.2} seconds",
+                wait.as_secs_f64()
+            );
+            task::sleep(wait).await;
+        }
+    }
+
+    /// Keep the time at which the rate limit bucket resets, if no requests are left
+    fn update_rate_limit(&self, headers: &HeaderMap) {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse::<f64>().ok())
+        };
+        if header("x-ratelimit-remaining") == Some(0.0) {
+            let reset_after = header("x-ratelimit-reset-after").unwrap_or(1.0);
+            *self.rate_limited_until.lock().unwrap() =
+                Some(Instant::now() + Duration::from_secs_f64(reset_after.max(0.0)));
+        }
+    }
+
+    /// Send a message, respecting the rate limits defined by Discord response headers
+    /// and waiting as requested by Discord when rate limited anyway
+    async fn dispatch_message(
+        &self,
+        message: &CreateMessage,
+    ) -> Result<(), DiscordError> {
+        let mut attempt: u32 = 0;
+        loop {
+            self.wait_rate_limit().await;
+            let req = match &self.destination {
+                Destination::Webhook(url) => {
+                    self.client.post(url).query(&[("wait", "true")])
+                }
+                Destination::Channel {
+                    bot_token,
+                    channel_id,
+                } => self
+                    .client
+                    .post(format!(
+                        "{}/channels/{}/messages",
+                        DISCORD_API_URL, channel_id
+                    ))
+                    .header("Authorization", format!("Bot {}", bot_token)),
+            };
+            let res = req.json(message).send().await?;
+            self.update_rate_limit(res.headers());
+            let status = res.status();
+
+            match status {
+                _ if status.is_success() => {
+                    debug!("Discord message dispatched");
+                    return Ok(());
+                }
+                reqwest::StatusCode::TOO_MANY_REQUESTS
+                    if attempt < DISCORD_MAX_RETRIES =>
+                {
+                    let response =
+                        res.json::<RateLimitResponse>().await.unwrap_or_default();
+                    let retry_after = response.retry_after.unwrap_or(5.0).max(0.0);
+                    warn!(
+                        "Discord {} -> Wait {:.2} seconds and try again",
+                        response.message, retry_after
+                    );
+                    task::sleep(Duration::from_secs_f64(retry_after)).await;
+                    attempt += 1;
+                }
+                _ => {
+                    let body = res.text().await.unwrap_or_default();
+                    return Err(DiscordError::Other(format!("{} ({})", body, status)));
+                }
+            }
+        }
+    }
+
+    /// Send every message in order, stopping at the first one that fails
+    async fn dispatch_messages(
+        &self,
+        messages: Vec<CreateMessage>,
+    ) -> Result<(), ScoutyError> {
+        for message in messages {
+            if let Err(e) = self.dispatch_message(&message).await {
+                warn!("Discord message not sent: {}", e);
+                return Err(e.into());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Notifier for Discord {
+    fn name(&self) -> &'static str {
+        "Discord"
+    }
+
+    async fn send_message(
+        &self,
+        _message: &str,
+        formatted_message: &str,
+    ) -> Result<(), ScoutyError> {
+        self.dispatch_messages(content_messages(formatted_message))
+            .await
+    }
+
+    /// Send the report summary as content, followed by an embed for each validator
+    async fn send_report(
+        &self,
+        report: &Report,
+        _matrix_user: Option<&str>,
+    ) -> Result<(), ScoutyError> {
+        let mut summary = report.summary();
+        summary.dedup_by(|a, b| a.is_empty() && b.is_empty());
+        let mut messages = content_messages(&summary.join("<br/>"));
+        for embeds in batch_embeds(report.cards().iter().map(Embed::from).collect()) {
+            messages.push(CreateMessage {
+                embeds,
+                ..Default::default()
+            });
+        }
+        self.dispatch_messages(messages).await
+    }
+}
+
+/// Returns the messages needed to send the text as content
+fn content_messages(formatted_message: &str) -> Vec<CreateMessage> {
+    let content = to_discord_markdown(formatted_message);
+    split_message(content.trim(), DISCORD_MAX_CONTENT_LENGTH)
+        .into_iter()
+        .map(|content| CreateMessage {
+            content,
+            ..Default::default()
+        })
+        .collect()
+}
+
+/// Group embeds in as few messages as possible, within Discord limits
+fn batch_embeds(embeds: Vec<Embed>) -> Vec<Vec<Embed>> {
+    let mut batches: Vec<Vec<Embed>> = Vec::new();
+    let mut batch: Vec<Embed> = Vec::new();
+    let mut batch_len = 0;
+    for embed in embeds {
+        if !batch.is_empty()
+            && (batch.len() == DISCORD_MAX_EMBEDS
+                || batch_len + embed.len() > DISCORD_MAX_EMBEDS_LENGTH)
+        {
+            batches.push(std::mem::take(&mut batch));
+            batch_len = 0;
+        }
+        batch_len += embed.len();
+        batch.push(embed);
+    }
+    if !batch.is_empty() {
+        batches.push(batch);
+    }
+    batches
+}
+
+fn status_color(status: Status) -> u32 {
+    match status {
+        Status::Active => 0x2ecc71,
+        Status::Inactive => 0x95a5a6,
+        Status::Slashed => 0xe74c3c,
+        Status::Chilled => 0x3498db,
+        Status::Offline => 0xe67e22,
+    }
+}
+
+/// Truncate text to at most max characters
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut truncated: String = text.chars().take(max.saturating_sub(1)).collect();
+    truncated.push('…');
+    truncated
+}
+
+/// Translate the HTML of a report into Discord markdown, tags without an
+/// equivalent are removed
+pub fn to_discord_markdown(html: &str) -> String {
+    let mut text = String::new();
+    // NOTE: the href of each open link, links without href are kept as text
+    let mut links: Vec<Option<String>> = Vec::new();
+    for node in parse_html(html) {
+        let tag = match node {
+            Html::Text(t) => {
+                text.push_str(&unescape_text(t));
+                continue;
+            }
+            Html::Tag(tag) => tag,
+        };
+        let is_closing = tag.is_closing;
+        match tag.name.as_str() {
+            "br" => text.push('\n'),
+            "b" | "strong" => text.push_str("**"),
+            "i" | "em" => text.push('*'),
+            "u" | "ins" => text.push_str("__"),
+            "s" | "strike" | "del" => text.push_str("~~"),
+            "code" => text.push('`'),
+            "pre" => text.push_str("```"),
+            "a" if is_closing => {
+                if let Some(Some(href)) = links.pop() {
+                    text.push_str(&format!("]({})", href));
+                }
+            }
+            "a" => match tag.href() {
+                Some(href) => {
+                    links.push(Some(unescape_text(href)));
+                    text.push('[');
+                }
+                None => links.push(None),
+            },
+            _ => (),
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_translates_report_html_to_discord_markdown() {
+        assert_eq!(
+            to_discord_markdown(
+                "🤖 <code>scouty</code><br/><br/>🟢 <b><a href=\"https://polkadot.subscan.io/validator/1\">Validator</a></b><br/>‣ cpu &lt; 80% &amp; <i>ok</i> <a>fine</a>"
+            ),
+            "🤖 `scouty`\n\n🟢 **[Validator](https://polkadot.subscan.io/validator/1)**\n‣ cpu < 80% & *ok* fine"
+        );
+    }
+
+    #[test]
+    fn it_batches_embeds_within_discord_limits() {
+        let embed = |description: &str| Embed {
+            title: "Validator".to_string(),
+            url: "https://polkadot.subscan.io/validator/1".to_string(),
+            color: status_color(Status::Active),
+            description: description.to_string(),
+            fields: Vec::new(),
+        };
+        let batches = batch_embeds(vec![embed(""); 12]);
+        assert_eq!(
+            batches.iter().map(|b| b.len()).collect::<Vec<usize>>(),
+            vec![10, 2]
+        );
+        let long = "x".repeat(3000);
+        let batches = batch_embeds(vec![embed(&long), embed(&long), embed("")]);
+        assert_eq!(
+            batches.iter().map(|b| b.len()).collect::<Vec<usize>>(),
+            vec![1, 2]
+        );
+        assert_eq!(truncate("abcdef", 4), "abc…");
+    }
+}
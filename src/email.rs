@@ -0,0 +1,327 @@
+// The MIT License (MIT)
+// Copyright © 2021 Aukbit Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::config::CONFIG;
+use crate::errors::{EmailError, ScoutyError};
+use crate::hooks::Severity;
+use crate::notifier::{parse_html, unescape_text, Html, Notifier};
+use crate::report::Report;
+use async_trait::async_trait;
+use lettre::{
+    message::{Mailbox, MultiPart},
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncStd1Executor, AsyncTransport, Message,
+};
+use log::{debug, error, info, warn};
+use serde::Deserialize;
+use std::{convert::TryFrom, result::Result, str::FromStr, time::Duration};
+
+/// Maximum time to connect and talk to the SMTP server
+const SMTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How the connection to the SMTP server is secured
+#[derive(Clone, Copy, Debug, PartialEq, Default, Deserialize)]
+#[serde(try_from = "String")]
+pub enum SmtpSecurity {
+    #[default]
+    StartTls,
+    Tls,
+    None,
+}
+
+impl SmtpSecurity {
+    /// Returns the port used when none is defined
+    fn default_port(&self) -> u16 {
+        match self {
+            SmtpSecurity::StartTls => 587,
+            SmtpSecurity::Tls => 465,
+            SmtpSecurity::None => 25,
+        }
+    }
+}
+
+impl FromStr for SmtpSecurity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "starttls" => Ok(SmtpSecurity::StartTls),
+            "tls" => Ok(SmtpSecurity::Tls),
+            "none" => Ok(SmtpSecurity::None),
+            _ => Err(format!(
+                "invalid smtp security {}, expected starttls, tls or none",
+                s
+            )),
+        }
+    }
+}
+
+impl TryFrom<String> for SmtpSecurity {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// Parse email addresses, with or without a display name (e.g. Scouty <scouty@example.com>)
+pub fn parse_mailboxes(addresses: &[String]) -> Result<Vec<Mailbox>, String> {
+    addresses
+        .iter()
+        .map(|address| {
+            address
+                .parse::<Mailbox>()
+                .map_err(|e| format!("invalid email address {}: {}", address, e))
+        })
+        .collect()
+}
+
+pub struct Email {
+    transport: AsyncSmtpTransport<AsyncStd1Executor>,
+    from: Mailbox,
+    to: Vec<Mailbox>,
+    cc: Vec<Mailbox>,
+    subject: String,
+}
+
+impl Email {
+    /// Returns an email notifier if an SMTP server and recipients are defined
+    pub fn new() -> Option<Email> {
+        let config = CONFIG.clone();
+        if config.email_disabled || config.smtp_host.is_empty() {
+            return None;
+        }
+        match Email::build() {
+            Ok(email) => {
+                info!(
+                    "Emails will be sent through {} to {}",
+                    config.smtp_host,
+                    config.email_to.join(", ")
+                );
+                Some(email)
+            }
+            Err(e) => {
+                error!("Emails will not be sent: {}", e);
+                None
+            }
+        }
+    }
+
+    fn build() -> Result<Email, EmailError> {
+        let config = CONFIG.clone();
+        let from = config
+            .email_from
+            .parse::<Mailbox>()
+            .map_err(|e| EmailError::Other(format!("invalid from address: {}", e)))?;
+        let to = parse_mailboxes(&config.email_to).map_err(EmailError::Other)?;
+        let cc = parse_mailboxes(&config.email_cc).map_err(EmailError::Other)?;
+
+        let builder = match config.smtp_security {
+            SmtpSecurity::StartTls => {
+                AsyncSmtpTransport::<AsyncStd1Executor>::starttls_relay(
+                    &config.smtp_host,
+                )?
+            }
+            SmtpSecurity::Tls => {
+                AsyncSmtpTransport::<AsyncStd1Executor>::relay(&config.smtp_host)?
+            }
+            SmtpSecurity::None => {
+                AsyncSmtpTransport::<AsyncStd1Executor>::builder_dangerous(
+                    &config.smtp_host,
+                )
+            }
+        };
+        let port = if config.smtp_port == 0 {
+            config.smtp_security.default_port()
+        } else {
+            config.smtp_port
+        };
+        let mut builder = builder.port(port).timeout(Some(SMTP_TIMEOUT));
+        if !config.smtp_username.is_empty() {
+            builder = builder.credentials(Credentials::new(
+                config.smtp_username.clone(),
+                config.smtp_password.clone(),
+            ));
+        }
+
+        Ok(Email {
+            transport: builder.build(),
+            from,
+            to,
+            cc,
+            subject: config.email_subject,
+        })
+    }
+
+    /// Send a multipart email with the plain text and HTML versions of a message
+    async fn dispatch_email(
+        &self,
+        subject: &str,
+        formatted_message: &str,
+    ) -> Result<(), EmailError> {
+        let mut builder = Message::builder().from(self.from.clone()).subject(subject);
+        for to in self.to.iter() {
+            builder = builder.to(to.clone());
+        }
+        for cc in self.cc.iter() {
+            builder = builder.cc(cc.clone());
+        }
+        let email = builder.multipart(MultiPart::alternative_plain_html(
+            to_plain_text(formatted_message),
+            format!(
+                "<!DOCTYPE html><html><body>{}</body></html>",
+                formatted_message
+            ),
+        ))?;
+
+        let response = self.transport.send(email).await?;
+        debug!("Email dispatched {:?}", response.code());
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Notifier for Email {
+    fn name(&self) -> &'static str {
+        "Email"
+    }
+
+    /// Send the message with its plain text as subject
+    async fn send_message(
+        &self,
+        message: &str,
+        formatted_message: &str,
+    ) -> Result<(), ScoutyError> {
+        let subject = format!("scouty: {}", message.lines().next().unwrap_or_default());
+        self.dispatch_email(&subject, formatted_message)
+            .await
+            .map_err(|e| {
+                warn!("Email not sent: {}", e);
+                e.into()
+            })
+    }
+
+    /// Send the report with the subject template filled in with its chain, era and section
+    async fn send_report(
+        &self,
+        report: &Report,
+        _matrix_user: Option<&str>,
+    ) -> Result<(), ScoutyError> {
+        let subject = render_subject(
+            &self.subject,
+            report.chain(),
+            report.era(),
+            &report
+                .section()
+                .map(|section| section.to_string())
+                .unwrap_or_default(),
+            report.severity(),
+        );
+        self.dispatch_email(&subject, &report.formatted_message())
+            .await
+            .map_err(|e| {
+                warn!("Email not sent: {}", e);
+                e.into()
+            })
+    }
+}
+
+/// Fill in the {chain}, {era}, {section} and {severity} placeholders of a subject template
+fn render_subject(
+    template: &str,
+    chain: &str,
+    era: Option<u32>,
+    section: &str,
+    severity: Severity,
+) -> String {
+    template
+        .replace("{chain}", chain)
+        .replace(
+            "{era}",
+            &era.map(|era| era.to_string())
+                .unwrap_or_else(|| "-".to_string()),
+        )
+        .replace("{section}", section)
+        .replace("{severity}", &severity.to_string())
+}
+
+/// Render the HTML of a report as plain text, with links followed by their url
+pub fn to_plain_text(html: &str) -> String {
+    let mut text = String::new();
+    // NOTE: the href of each open link, links without href are kept as text
+    let mut links: Vec<Option<String>> = Vec::new();
+    for node in parse_html(html) {
+        let tag = match node {
+            Html::Text(t) => {
+                text.push_str(&unescape_text(t));
+                continue;
+            }
+            Html::Tag(tag) => tag,
+        };
+        let is_closing = tag.is_closing;
+        match tag.name.as_str() {
+            "br" => text.push('\n'),
+            "a" if is_closing => {
+                if let Some(Some(href)) = links.pop() {
+                    text.push_str(&format!(" ({})", href));
+                }
+            }
+            "a" => links.push(tag.href().map(unescape_text)),
+            _ => (),
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_renders_report_html_as_plain_text() {
+        assert_eq!(
+            to_plain_text(
+                "🤖 <code>scouty</code><br/><br/>🟢 <b><a href=\"https://polkadot.subscan.io/validator/1\">Validator</a></b><br/>‣ cpu &lt; 80% &amp; <i>ok</i>"
+            ),
+            "🤖 scouty\n\n🟢 Validator (https://polkadot.subscan.io/validator/1)\n‣ cpu < 80% & ok"
+        );
+    }
+
+    #[test]
+    fn it_renders_subject_templates() {
+        assert_eq!(
+            render_subject(
+                "[{chain}] {section} in era {era} ({severity})",
+                "Polkadot",
+                Some(1234),
+                "Slash",
+                Severity::Critical
+            ),
+            "[Polkadot] Slash in era 1234 (Critical)"
+        );
+        assert_eq!(
+            render_subject("{chain} era {era}", "Paseo", None, "", Severity::Info),
+            "Paseo era -"
+        );
+        assert!("STARTTLS".parse::<SmtpSecurity>().is_ok());
+        assert!("ssl".parse::<SmtpSecurity>().is_err());
+    }
+}
@@ -19,8 +19,6 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use codec;
-use reqwest;
 use std::{str, string, string::String};
 use subxt::error::MetadataError;
 use thiserror::Error;
@@ -28,8 +26,9 @@ use thiserror::Error;
 /// Scouty specific error messages
 #[derive(Error, Debug)]
 pub enum ScoutyError {
+    // NOTE: subxt errors are boxed to keep results small
     #[error("Subxt error: {0}")]
-    SubxtError(#[from] subxt::Error),
+    SubxtError(Box<subxt::Error>),
     #[error("Codec error: {0}")]
     CodecError(#[from] codec::Error),
     #[error("IO error: {0}")]
@@ -40,14 +39,33 @@ pub enum ScoutyError {
     Utf8Error(#[from] str::Utf8Error),
     #[error("Metadata error: {0}")]
     MetadataError(#[from] MetadataError),
+    #[error("Reqwest error: {0}")]
+    ReqwestError(#[from] reqwest::Error),
     #[error("Matrix error: {0}")]
     MatrixError(String),
+    #[error("Telegram error: {0}")]
+    TelegramError(String),
+    #[error("Discord error: {0}")]
+    DiscordError(String),
+    #[error("Slack error: {0}")]
+    SlackError(String),
+    #[error("Email error: {0}")]
+    EmailError(String),
     #[error("Subscription finished")]
     SubscriptionFinished,
+    #[error("Endpoint unhealthy: {0}")]
+    EndpointUnhealthy(String),
     #[error("Other error: {0}")]
     Other(String),
 }
 
+/// Convert subxt::Error to ScoutyError
+impl From<subxt::Error> for ScoutyError {
+    fn from(error: subxt::Error) -> Self {
+        ScoutyError::SubxtError(Box::new(error))
+    }
+}
+
 /// Convert &str to ScoutyError
 impl From<&str> for ScoutyError {
     fn from(error: &str) -> Self {
@@ -79,3 +97,99 @@ impl From<MatrixError> for ScoutyError {
         ScoutyError::MatrixError(error.into())
     }
 }
+
+/// Telegram specific error messages
+#[derive(Error, Debug)]
+pub enum TelegramError {
+    #[error("Reqwest error: {0}")]
+    ReqwestError(reqwest::Error),
+    #[error("{0}")]
+    Other(String),
+}
+
+/// Convert reqwest::Error to TelegramError, without the url since it contains the bot token
+impl From<reqwest::Error> for TelegramError {
+    fn from(error: reqwest::Error) -> Self {
+        TelegramError::ReqwestError(error.without_url())
+    }
+}
+
+/// Convert TelegramError to ScoutyError
+impl From<TelegramError> for ScoutyError {
+    fn from(error: TelegramError) -> Self {
+        ScoutyError::TelegramError(error.to_string())
+    }
+}
+
+/// Discord specific error messages
+#[derive(Error, Debug)]
+pub enum DiscordError {
+    #[error("Reqwest error: {0}")]
+    ReqwestError(reqwest::Error),
+    #[error("{0}")]
+    Other(String),
+}
+
+/// Convert reqwest::Error to DiscordError, without the url since a webhook url contains its token
+impl From<reqwest::Error> for DiscordError {
+    fn from(error: reqwest::Error) -> Self {
+        DiscordError::ReqwestError(error.without_url())
+    }
+}
+
+/// Convert DiscordError to ScoutyError
+impl From<DiscordError> for ScoutyError {
+    fn from(error: DiscordError) -> Self {
+        ScoutyError::DiscordError(error.to_string())
+    }
+}
+
+/// Slack specific error messages
+#[derive(Error, Debug)]
+pub enum SlackError {
+    #[error("Reqwest error: {0}")]
+    ReqwestError(reqwest::Error),
+    #[error("{0}")]
+    Other(String),
+}
+
+/// Convert reqwest::Error to SlackError, without the url since a webhook url contains its token
+impl From<reqwest::Error> for SlackError {
+    fn from(error: reqwest::Error) -> Self {
+        SlackError::ReqwestError(error.without_url())
+    }
+}
+
+/// Convert SlackError to ScoutyError
+impl From<SlackError> for ScoutyError {
+    fn from(error: SlackError) -> Self {
+        ScoutyError::SlackError(error.to_string())
+    }
+}
+
+/// Email specific error messages
+#[derive(Error, Debug)]
+pub enum EmailError {
+    #[error("SMTP error: {0}")]
+    SmtpError(#[from] lettre::transport::smtp::Error),
+    #[error("Message error: {0}")]
+    MessageError(#[from] lettre::error::Error),
+    #[error("{0}")]
+    Other(String),
+}
+
+/// Convert EmailError to ScoutyError
+impl From<EmailError> for ScoutyError {
+    fn from(error: EmailError) -> Self {
+        ScoutyError::EmailError(error.to_string())
+    }
+}
+
+/// Incident backends specific error messages
+#[derive(Error, Debug)]
+pub enum IncidentError {
+    #[error("Reqwest error: {0}")]
+    ReqwestError(#[from] reqwest::Error),
+    #[error("{0}")]
+    Other(String),
+}
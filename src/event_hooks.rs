@@ -0,0 +1,243 @@
+// The MIT License (MIT)
+// Copyright © 2021 Aukbit Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+// Hooks executed on any chain event matching a filter defined in the event hooks
+// config file, e.g. `Balances.Transfer where from in stashes`.
+//
+// Filter syntax: `<Pallet>.<Event> [where <condition> [and <condition> ..]]`, with
+// conditions of the form `<field> in stashes`, `<field> not in stashes`,
+// `<field> == <value>` or `<field> != <value>`. Fields are the event fields as
+// decoded from the runtime metadata, nested fields are reached by '.' and
+// unnamed fields by their position (e.g. `0`).
+//
+use serde::Deserialize;
+use serde_json::Value;
+use std::{convert::TryFrom, fmt, str::FromStr};
+use subxt::utils::AccountId32;
+
+/// Condition on an event field
+#[derive(Clone, Debug, PartialEq)]
+pub enum Condition {
+    InStashes(String),
+    NotInStashes(String),
+    Equal(String, String),
+    NotEqual(String, String),
+}
+
+impl FromStr for Condition {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens: Vec<&str> = s.split_whitespace().collect();
+        match tokens[..] {
+            [field, "in", "stashes"] => Ok(Condition::InStashes(field.into())),
+            [field, "not", "in", "stashes"] => Ok(Condition::NotInStashes(field.into())),
+            [field, "==", value] => Ok(Condition::Equal(field.into(), unquote(value))),
+            [field, "!=", value] => Ok(Condition::NotEqual(field.into(), unquote(value))),
+            _ => Err(format!("invalid condition '{}'", s.trim())),
+        }
+    }
+}
+
+impl Condition {
+    /// Returns true if the event fields satisfy the condition
+    fn is_met(&self, fields: &Value, stashes: &[AccountId32]) -> bool {
+        match self {
+            Condition::InStashes(name) => field(fields, name)
+                .map(|value| is_stash(value, stashes))
+                .unwrap_or(false),
+            Condition::NotInStashes(name) => field(fields, name)
+                .map(|value| !is_stash(value, stashes))
+                .unwrap_or(false),
+            Condition::Equal(name, expected) => field(fields, name)
+                .map(|value| to_string(value) == *expected)
+                .unwrap_or(false),
+            Condition::NotEqual(name, expected) => field(fields, name)
+                .map(|value| to_string(value) != *expected)
+                .unwrap_or(false),
+        }
+    }
+}
+
+/// Event name and conditions that a chain event must satisfy for a hook to run
+#[derive(Clone, Debug, Deserialize)]
+#[serde(try_from = "String")]
+pub struct EventFilter {
+    pub pallet: String,
+    pub event: String,
+    pub conditions: Vec<Condition>,
+}
+
+impl FromStr for EventFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, conditions) = match s.split_once(" where ") {
+            Some((name, conditions)) => (name, Some(conditions)),
+            None => (s, None),
+        };
+        let (pallet, event) = match name.trim().split_once('.') {
+            Some((pallet, event)) if !pallet.is_empty() && !event.is_empty() => {
+                (pallet, event)
+            }
+            _ => {
+                return Err(format!(
+                    "invalid event '{}', expected <Pallet>.<Event>",
+                    name.trim()
+                ))
+            }
+        };
+        let conditions = match conditions {
+            Some(conditions) => conditions
+                .split(" and ")
+                .map(Condition::from_str)
+                .collect::<Result<Vec<Condition>, String>>()?,
+            None => vec![],
+        };
+        Ok(EventFilter {
+            pallet: pallet.to_string(),
+            event: event.to_string(),
+            conditions,
+        })
+    }
+}
+
+impl TryFrom<String> for EventFilter {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl fmt::Display for EventFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.pallet, self.event)
+    }
+}
+
+impl EventFilter {
+    /// Returns true if pallet and event names match, names are case insensitive (e.g. balances.Transfer)
+    pub fn is_event(&self, pallet: &str, event: &str) -> bool {
+        self.pallet.eq_ignore_ascii_case(pallet) && self.event.eq_ignore_ascii_case(event)
+    }
+
+    /// Returns true if the event fields satisfy every condition
+    pub fn matches(&self, fields: &Value, stashes: &[AccountId32]) -> bool {
+        self.conditions.iter().all(|c| c.is_met(fields, stashes))
+    }
+}
+
+/// Hook script executed for every chain event matching the filter
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EventHook {
+    pub event: EventFilter,
+    pub path: String,
+}
+
+/// Returns the event field by name, nested by '.' (e.g. dest.Account)
+fn field<'a>(fields: &'a Value, name: &str) -> Option<&'a Value> {
+    name.split('.').try_fold(fields, |value, key| match value {
+        Value::Object(map) => map.get(key),
+        Value::Array(values) => key.parse::<usize>().ok().and_then(|i| values.get(i)),
+        _ => None,
+    })
+}
+
+/// Returns true if the field value, or any of its values, is one of the stashes
+fn is_stash(value: &Value, stashes: &[AccountId32]) -> bool {
+    match value {
+        Value::String(s) => AccountId32::from_str(s)
+            .map(|account| stashes.contains(&account))
+            .unwrap_or(false),
+        Value::Array(values) => values.iter().any(|v| is_stash(v, stashes)),
+        _ => false,
+    }
+}
+
+fn to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.to_string(),
+        _ => value.to_string(),
+    }
+}
+
+fn unquote(value: &str) -> String {
+    value.trim_matches(|c| c == '"' || c == '\'').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn it_parses_event_filters() {
+        let filter: EventFilter =
+            "balances.Transfer where from in stashes and amount != 0"
+                .parse()
+                .unwrap();
+        assert_eq!(filter.to_string(), "balances.Transfer");
+        assert!(filter.is_event("Balances", "Transfer"));
+        assert!(!filter.is_event("Balances", "Deposit"));
+        assert_eq!(
+            filter.conditions,
+            vec![
+                Condition::InStashes("from".into()),
+                Condition::NotEqual("amount".into(), "0".into())
+            ]
+        );
+        assert!("Staking.Rewarded".parse::<EventFilter>().is_ok());
+        assert!("Rewarded where stash in stashes"
+            .parse::<EventFilter>()
+            .is_err());
+        assert!("Staking.Rewarded where stash is mine"
+            .parse::<EventFilter>()
+            .is_err());
+    }
+
+    #[test]
+    fn it_matches_event_fields() {
+        let stash =
+            AccountId32::from_str("5GTD7ZeD823BjpmZBCSzBQp7cvHR1Gunq7oDkurZr9zUev2n")
+                .unwrap();
+        let fields = json!({
+            "stash": "5GTD7ZeD823BjpmZBCSzBQp7cvHR1Gunq7oDkurZr9zUev2n",
+            "dest": { "Account": "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty" },
+            "amount": 1000
+        });
+        let filter: EventFilter =
+            "Staking.Rewarded where stash in stashes and amount == 1000"
+                .parse()
+                .unwrap();
+        assert!(filter.matches(&fields, std::slice::from_ref(&stash)));
+        assert!(!filter.matches(&fields, &[]));
+
+        let filter: EventFilter = "Staking.Rewarded where dest.Account not in stashes"
+            .parse()
+            .unwrap();
+        assert!(filter.matches(&fields, &[stash]));
+
+        let filter: EventFilter = "Staking.Rewarded where unknown == 1".parse().unwrap();
+        assert!(!filter.matches(&fields, &[]));
+    }
+}
@@ -0,0 +1,483 @@
+// The MIT License (MIT)
+// Copyright © 2021 Aukbit Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::config::CONFIG;
+use crate::errors::IncidentError;
+use crate::opsgenie::Opsgenie;
+use crate::pagerduty::PagerDuty;
+use crate::report::{RawData, Section, Validator};
+use async_std::task;
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use log::{debug, info, warn};
+use serde::Deserialize;
+use std::{
+    collections::HashMap, convert::TryFrom, fmt, result::Result, str::FromStr,
+    sync::Mutex, time::Duration,
+};
+use subxt::utils::AccountId32;
+
+/// Maximum number of times an incident event is retried when rate limited
+const INCIDENT_MAX_RETRIES: u32 = 3;
+
+lazy_static! {
+    // NOTE: state of the incidents by dedup key, true while open and false once resolved,
+    // kept across reconnects. Incidents missing after a restart are resolved on the next
+    // clear observed, backends ignore dedup keys they do not know.
+    static ref INCIDENT_STATES: Mutex<HashMap<String, bool>> = Mutex::new(HashMap::new());
+}
+
+/// Validator condition for which an incident is opened
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+#[serde(try_from = "String")]
+pub enum Condition {
+    Slashed,
+    Chilled,
+    StartsInactiveNextEra,
+    Offline,
+}
+
+impl Condition {
+    pub fn all() -> Vec<Condition> {
+        vec![
+            Condition::Slashed,
+            Condition::Chilled,
+            Condition::StartsInactiveNextEra,
+            Condition::Offline,
+        ]
+    }
+
+    /// Returns the condition as used in dedup keys
+    pub fn key(&self) -> &'static str {
+        match self {
+            Condition::Slashed => "slashed",
+            Condition::Chilled => "chilled",
+            Condition::StartsInactiveNextEra => "starts_inactive_next_era",
+            Condition::Offline => "offline",
+        }
+    }
+}
+
+impl FromStr for Condition {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Condition::all()
+            .into_iter()
+            .find(|condition| condition.key() == s.trim().to_lowercase())
+            .ok_or_else(|| {
+                format!(
+                    "invalid incident condition {}, expected slashed, chilled, starts_inactive_next_era or offline",
+                    s
+                )
+            })
+    }
+}
+
+impl TryFrom<String> for Condition {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Condition::Slashed => write!(f, "has been slashed"),
+            Condition::Chilled => write!(f, "has been chilled"),
+            Condition::StartsInactiveNextEra => write!(f, "starts inactive next era"),
+            Condition::Offline => write!(f, "has been seen offline"),
+        }
+    }
+}
+
+/// Incident of a validator stash, identified by a dedup key stable across events
+#[derive(Clone, Debug)]
+pub struct Incident {
+    pub dedup_key: String,
+    pub condition: Condition,
+    pub chain: String,
+    pub stash: AccountId32,
+    pub name: String,
+    pub url: String,
+    pub details: Vec<String>,
+}
+
+impl Incident {
+    fn new(chain: &str, validator: &Validator, condition: Condition) -> Self {
+        Self {
+            dedup_key: format!(
+                "scouty-{}-{}-{}",
+                chain.to_lowercase(),
+                validator.stash,
+                condition.key()
+            ),
+            condition,
+            chain: chain.to_string(),
+            stash: validator.stash.clone(),
+            name: validator.display_name(),
+            url: format!(
+                "https://{}.subscan.io/validator/{}",
+                chain.to_lowercase(),
+                validator.stash
+            ),
+            details: validator.offline_signals.clone(),
+        }
+    }
+
+    /// Returns a one line description of the incident
+    pub fn summary(&self) -> String {
+        format!(
+            "{} validator {} ({}) {}",
+            self.chain, self.name, self.stash, self.condition
+        )
+    }
+}
+
+/// Incident management service to which incidents are triggered and resolved
+#[async_trait]
+pub trait IncidentBackend: Send + Sync {
+    /// Returns the backend name, as shown in logs
+    fn name(&self) -> &'static str;
+
+    async fn trigger(&self, incident: &Incident) -> Result<(), IncidentError>;
+
+    async fn resolve(&self, incident: &Incident) -> Result<(), IncidentError>;
+}
+
+pub struct Incidents {
+    backends: Vec<Box<dyn IncidentBackend>>,
+    conditions: Vec<Condition>,
+}
+
+impl Incidents {
+    pub fn new() -> Self {
+        let config = CONFIG.clone();
+        let mut backends: Vec<Box<dyn IncidentBackend>> = Vec::new();
+        if !config.incidents_disabled {
+            if let Some(pagerduty) = PagerDuty::new() {
+                backends.push(Box::new(pagerduty));
+            }
+            if let Some(opsgenie) = Opsgenie::new() {
+                backends.push(Box::new(opsgenie));
+            }
+        }
+        if !backends.is_empty() {
+            info!(
+                "Incidents will be opened for validators that {}",
+                config
+                    .incident_conditions
+                    .iter()
+                    .map(|c| c.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            );
+        }
+        Self {
+            backends,
+            conditions: config.incident_conditions,
+        }
+    }
+
+    /// Returns true if incidents are sent for the condition
+    pub fn is_tracked(&self, condition: Condition) -> bool {
+        !self.backends.is_empty() && self.conditions.contains(&condition)
+    }
+
+    /// Trigger an incident for every condition raised and resolve the incidents
+    /// for every condition cleared. Failures are only logged, so that notifications
+    /// are still sent.
+    pub async fn track(&self, data: &RawData) {
+        for (validator, condition, is_raised) in observe(data) {
+            if !self.is_tracked(condition) {
+                continue;
+            }
+            let incident = Incident::new(&data.network.name, validator, condition);
+            if is_raised {
+                self.trigger(&incident).await;
+            } else {
+                self.resolve(&incident).await;
+            }
+        }
+    }
+
+    /// Resolve the incidents of a condition observed as cleared for the stashes
+    pub async fn clear(
+        &self,
+        chain: &str,
+        stashes: &[AccountId32],
+        condition: Condition,
+    ) {
+        if !self.is_tracked(condition) {
+            return;
+        }
+        for stash in stashes.iter() {
+            let validator = Validator::new(stash.clone());
+            self.resolve(&Incident::new(chain, &validator, condition))
+                .await;
+        }
+    }
+
+    async fn trigger(&self, incident: &Incident) {
+        // NOTE: incidents already open are triggered again, backends group them by dedup key
+        let mut is_accepted = false;
+        for backend in self.backends.iter() {
+            match backend.trigger(incident).await {
+                Ok(()) => is_accepted = true,
+                Err(e) => warn!("{} incident not triggered: {}", backend.name(), e),
+            }
+        }
+        if is_accepted {
+            INCIDENT_STATES
+                .lock()
+                .unwrap()
+                .insert(incident.dedup_key.clone(), true);
+        }
+    }
+
+    async fn resolve(&self, incident: &Incident) {
+        // Skip incidents already resolved
+        if INCIDENT_STATES.lock().unwrap().get(&incident.dedup_key) == Some(&false) {
+            return;
+        }
+        let mut is_accepted = false;
+        for backend in self.backends.iter() {
+            match backend.resolve(incident).await {
+                Ok(()) => is_accepted = true,
+                Err(e) => warn!("{} incident not resolved: {}", backend.name(), e),
+            }
+        }
+        if is_accepted {
+            INCIDENT_STATES
+                .lock()
+                .unwrap()
+                .insert(incident.dedup_key.clone(), false);
+        }
+    }
+}
+
+/// Returns every condition raised (true) or cleared (false) for each validator in the data
+fn observe(data: &RawData) -> Vec<(&Validator, Condition, bool)> {
+    let mut observations = Vec::new();
+    for v in data.validators.iter() {
+        match data.section {
+            Section::Slash if v.is_slashed => {
+                observations.push((v, Condition::Slashed, true));
+            }
+            Section::Chill if v.is_chilled => {
+                observations.push((v, Condition::Chilled, true));
+            }
+            Section::Offline => {
+                observations.push((v, Condition::Offline, v.is_offline));
+            }
+            Section::Session => {
+                // NOTE: the stash being queued when the next validator set is queued
+                // means it has been elected again
+                if data.session.queued_session_keys_changed && v.is_queued {
+                    observations.push((v, Condition::Slashed, false));
+                    observations.push((v, Condition::Chilled, false));
+                }
+                if data.session.is_last_session_of_era()
+                    && data.session.queued_session_keys_changed
+                    && v.is_active
+                    && !v.is_queued
+                {
+                    observations.push((v, Condition::StartsInactiveNextEra, true));
+                } else if v.is_active && v.is_queued {
+                    observations.push((v, Condition::StartsInactiveNextEra, false));
+                }
+            }
+            _ => (),
+        }
+    }
+    observations
+}
+
+/// Send an incident event, waiting and trying again when rate limited
+pub async fn dispatch_event(
+    request: reqwest::RequestBuilder,
+) -> Result<(), IncidentError> {
+    let mut attempt: u32 = 0;
+    loop {
+        let res = request
+            .try_clone()
+            .ok_or_else(|| IncidentError::Other("request can not be sent".to_string()))?
+            .send()
+            .await?;
+        let status = res.status();
+
+        match status {
+            _ if status.is_success() => {
+                debug!("Incident event dispatched ({})", status);
+                return Ok(());
+            }
+            reqwest::StatusCode::TOO_MANY_REQUESTS if attempt < INCIDENT_MAX_RETRIES => {
+                let retry_after = res
+                    .headers()
+                    .get("retry-after")
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| v.parse::<u64>().ok())
+                    .unwrap_or(5);
+                warn!(
+                    "Incident rate limited -> Wait {} seconds and try again",
+                    retry_after
+                );
+                task::sleep(Duration::from_secs(retry_after)).await;
+                attempt += 1;
+            }
+            _ => {
+                let body = res.text().await.unwrap_or_default();
+                return Err(IncidentError::Other(format!("{} ({})", body, status)));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::Session;
+    use std::sync::Arc;
+
+    #[test]
+    fn it_observes_conditions_raised_and_cleared() {
+        let alice = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+        let bob = "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty";
+        let validator = |stash: &str, is_active: bool, is_queued: bool| {
+            let mut v = Validator::new(AccountId32::from_str(stash).unwrap());
+            v.is_active = is_active;
+            v.is_queued = is_queued;
+            v
+        };
+        let conditions = |data: &RawData| {
+            observe(data)
+                .into_iter()
+                .map(|(v, c, is_raised)| (v.stash.to_string(), c, is_raised))
+                .collect::<Vec<(String, Condition, bool)>>()
+        };
+
+        // Last session of the era, alice keys are not queued for the next era
+        let mut data = RawData {
+            validators: vec![validator(alice, true, false), validator(bob, false, false)],
+            session: Session {
+                eras_session_index: 6,
+                sessions_per_era: 6,
+                queued_session_keys_changed: true,
+                ..Default::default()
+            },
+            section: Section::Session,
+            ..Default::default()
+        };
+        assert_eq!(
+            conditions(&data),
+            vec![(alice.to_string(), Condition::StartsInactiveNextEra, true)]
+        );
+
+        // Next session, alice is active and keys are queued again
+        data.validators = vec![validator(alice, true, true)];
+        data.session.eras_session_index = 1;
+        data.session.queued_session_keys_changed = false;
+        assert_eq!(
+            conditions(&data),
+            vec![(alice.to_string(), Condition::StartsInactiveNextEra, false)]
+        );
+
+        let incident = Incident::new("Polkadot", &data.validators[0], Condition::Offline);
+        assert_eq!(
+            incident.dedup_key,
+            format!("scouty-polkadot-{}-offline", alice)
+        );
+        assert_eq!(
+            "starts_inactive_next_era".parse::<Condition>(),
+            Ok(Condition::StartsInactiveNextEra)
+        );
+    }
+
+    struct FakeBackend {
+        is_failing: bool,
+        events: Arc<Mutex<Vec<&'static str>>>,
+    }
+
+    #[async_trait]
+    impl IncidentBackend for FakeBackend {
+        fn name(&self) -> &'static str {
+            "Fake"
+        }
+
+        async fn trigger(&self, _incident: &Incident) -> Result<(), IncidentError> {
+            self.events.lock().unwrap().push("trigger");
+            if self.is_failing {
+                return Err(IncidentError::Other("unavailable".to_string()));
+            }
+            Ok(())
+        }
+
+        async fn resolve(&self, _incident: &Incident) -> Result<(), IncidentError> {
+            self.events.lock().unwrap().push("resolve");
+            Ok(())
+        }
+    }
+
+    #[async_std::test]
+    async fn it_resolves_incidents_unless_already_resolved() {
+        let stash =
+            AccountId32::from_str("5FLSigC9HGRKVhB9FiEo4Y3koPsNmBmLJbpXg2mp1hXcS59Y")
+                .unwrap();
+        let incident = Incident::new(
+            "Westend",
+            &Validator::new(stash.clone()),
+            Condition::Chilled,
+        );
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let incidents = |is_failing: bool| Incidents {
+            backends: vec![Box::new(FakeBackend {
+                is_failing,
+                events: events.clone(),
+            })],
+            conditions: Condition::all(),
+        };
+
+        // A trigger not accepted by any backend does not open the incident
+        incidents(true).trigger(&incident).await;
+        assert_eq!(
+            INCIDENT_STATES.lock().unwrap().get(&incident.dedup_key),
+            None
+        );
+
+        // Incidents of unknown state, e.g. opened before a restart, are resolved once
+        let incidents = incidents(false);
+        incidents
+            .clear("Westend", std::slice::from_ref(&stash), Condition::Chilled)
+            .await;
+        incidents
+            .clear("Westend", &[stash], Condition::Chilled)
+            .await;
+        incidents.trigger(&incident).await;
+        incidents.resolve(&incident).await;
+        incidents.resolve(&incident).await;
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec!["trigger", "resolve", "trigger", "resolve"]
+        );
+    }
+}
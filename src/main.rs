@@ -21,17 +21,28 @@
 
 mod authority;
 mod config;
+mod discord;
+mod email;
 mod errors;
+mod event_hooks;
 mod hooks;
 mod identity;
+mod incidents;
 mod matrix;
+mod notifier;
+mod opsgenie;
+mod pagerduty;
 mod para;
 mod report;
 mod runtimes;
+mod schedule;
 mod scouty;
+mod slack;
 mod stats;
+mod telegram;
 
 use crate::config::CONFIG;
+use crate::hooks::{hook_name, Hook};
 use crate::scouty::Scouty;
 use log::info;
 use std::env;
@@ -52,5 +63,18 @@ fn main() {
         env!("CARGO_PKG_DESCRIPTION")
     );
 
-    Scouty::subscribe();
+    if let Some(key) = config.hooks_describe.as_deref() {
+        Hook::print_description(key);
+        return;
+    }
+
+    if let Some(name) = config.hooks_test.as_deref().and_then(hook_name) {
+        Scouty::test_hook(name, config.hooks_test_at);
+        return;
+    }
+
+    match (config.replay_from, config.replay_to) {
+        (Some(from), Some(to)) => Scouty::replay(from, to),
+        _ => Scouty::subscribe(),
+    }
 }
@@ -20,9 +20,12 @@
 // SOFTWARE.
 #![allow(dead_code)]
 use crate::config::CONFIG;
-use crate::errors::MatrixError;
+use crate::errors::{MatrixError, ScoutyError};
+use crate::notifier::Notifier;
+use crate::report::Report;
 use crate::runtimes::support::SupportedRuntime;
 use async_recursion::async_recursion;
+use async_trait::async_trait;
 use base64::encode;
 use log::{debug, info, warn};
 use serde::{Deserialize, Serialize};
@@ -63,18 +66,18 @@ fn define_private_room_alias_name(
 }
 
 impl Room {
-    fn new_private(chain: SupportedRuntime) -> Room {
+    fn new_private(chain: SupportedRuntime, matrix_user: &str) -> Room {
         let config = CONFIG.clone();
         let room_alias_name = define_private_room_alias_name(
             env!("CARGO_PKG_NAME"),
             &chain.to_string(),
-            &config.matrix_user,
+            matrix_user,
             &config.matrix_bot_user,
         );
         let v: Vec<&str> = config.matrix_bot_user.split(":").collect();
         Room {
             room_alias_name: room_alias_name.to_string(),
-            room_alias: format!("#{}:{}", room_alias_name.to_string(), v.last().unwrap()),
+            room_alias: format!("#{}:{}", room_alias_name, v.last().unwrap()),
             ..Default::default()
         }
     }
@@ -140,6 +143,8 @@ pub struct Matrix {
     access_token: Option<String>,
     chain: SupportedRuntime,
     private_room_id: String,
+    // NOTE: private rooms of the matrix users defined for particular stashes
+    user_room_ids: HashMap<String, RoomID>,
     public_room_id: String,
     disabled: bool,
 }
@@ -151,6 +156,7 @@ impl Default for Matrix {
             access_token: None,
             chain: SupportedRuntime::Westend,
             private_room_id: String::from(""),
+            user_room_ids: HashMap::new(),
             public_room_id: String::from(""),
             disabled: false,
         }
@@ -171,7 +177,7 @@ impl Matrix {
             return Ok(());
         }
         let config = CONFIG.clone();
-        if let None = config.matrix_bot_user.find(":") {
+        if !config.matrix_bot_user.contains(':') {
             return Err(MatrixError::Other(format!("matrix bot user '{}' does specifed the matrix server e.g. '@your-own-scouty-bot-account:matrix.org'", config.matrix_bot_user)));
         }
         let client = self.client.clone();
@@ -250,13 +256,27 @@ impl Matrix {
         // Login
         self.login().await?;
         // Get or create user private room
-        if let Some(private_room) = self.get_or_create_private_room().await? {
+        if let Some(private_room) =
+            self.get_or_create_private_room(&config.matrix_user).await?
+        {
             self.private_room_id = private_room.room_id;
             info!(
                 "Messages will be sent to room {} (Private)",
                 private_room.room_alias
             );
         }
+        // Get or create private rooms of the matrix users defined for particular stashes
+        for matrix_user in config.stash_matrix_users() {
+            if let Some(private_room) =
+                self.get_or_create_private_room(&matrix_user).await?
+            {
+                info!(
+                    "Messages of {} stashes will be sent to room {} (Private)",
+                    matrix_user, private_room.room_alias
+                );
+                self.user_room_ids.insert(matrix_user, private_room.room_id);
+            }
+        }
         // Change Scouty Bot display name
         if !config.matrix_bot_display_name_disabled {
             self.change_bot_display_name().await?;
@@ -329,18 +349,20 @@ impl Matrix {
         }
     }
 
-    async fn create_private_room(&self) -> Result<Option<Room>, MatrixError> {
+    async fn create_private_room(
+        &self,
+        matrix_user: &str,
+    ) -> Result<Option<Room>, MatrixError> {
         match &self.access_token {
             Some(access_token) => {
-                let config = CONFIG.clone();
                 let client = self.client.clone();
-                let room: Room = Room::new_private(self.chain);
+                let room: Room = Room::new_private(self.chain, matrix_user);
                 let req = CreateRoomRequest {
                     name: format!("{} Scouty Bot (Private)", self.chain),
                     room_alias_name: room.room_alias_name.to_string(),
                     topic: "Scouty Bot <> Leading nodes every session".to_string(),
                     preset: "trusted_private_chat".to_string(),
-                    invite: vec![config.matrix_user],
+                    invite: vec![matrix_user.to_string()],
                     is_direct: true,
                 };
                 let res = client
@@ -371,16 +393,19 @@ impl Matrix {
         }
     }
 
-    async fn get_or_create_private_room(&self) -> Result<Option<Room>, MatrixError> {
+    async fn get_or_create_private_room(
+        &self,
+        matrix_user: &str,
+    ) -> Result<Option<Room>, MatrixError> {
         match &self.access_token {
             Some(_) => {
-                let mut room: Room = Room::new_private(self.chain);
+                let mut room: Room = Room::new_private(self.chain, matrix_user);
                 match self.get_room_id_by_room_alias(&room.room_alias).await? {
                     Some(room_id) => {
                         room.room_id = room_id;
                         Ok(Some(room))
                     }
-                    None => Ok(self.create_private_room().await?),
+                    None => Ok(self.create_private_room(matrix_user).await?),
                 }
             }
             None => Err(MatrixError::Other("access_token not defined".to_string())),
@@ -396,12 +421,38 @@ impl Matrix {
             return Ok(());
         }
         // Send message to private room (private assigned to the matrix_username in config)
-        self.dispatch_message(&self.private_room_id, &message, &formatted_message)
+        self.dispatch_message(&self.private_room_id, message, formatted_message)
             .await?;
 
         Ok(())
     }
 
+    /// Send message to the private room of a matrix user defined for particular stashes
+    pub async fn send_message_to_user(
+        &self,
+        matrix_user: &str,
+        message: &str,
+        formatted_message: &str,
+    ) -> Result<(), MatrixError> {
+        if self.disabled {
+            return Ok(());
+        }
+        match self.user_room_ids.get(matrix_user) {
+            Some(room_id) => {
+                self.dispatch_message(room_id, message, formatted_message)
+                    .await?;
+                Ok(())
+            }
+            None => {
+                warn!(
+                    "No private room for {}, message sent to the default private room",
+                    matrix_user
+                );
+                self.send_message(message, formatted_message).await
+            }
+        }
+    }
+
     #[async_recursion]
     async fn dispatch_message(
         &self,
@@ -464,3 +515,41 @@ impl Matrix {
         }
     }
 }
+
+#[async_trait]
+impl Notifier for Matrix {
+    fn name(&self) -> &'static str {
+        "Matrix"
+    }
+
+    async fn send_message(
+        &self,
+        message: &str,
+        formatted_message: &str,
+    ) -> Result<(), ScoutyError> {
+        Matrix::send_message(self, message, formatted_message).await?;
+        Ok(())
+    }
+
+    async fn send_report(
+        &self,
+        report: &Report,
+        matrix_user: Option<&str>,
+    ) -> Result<(), ScoutyError> {
+        match matrix_user {
+            Some(matrix_user) => {
+                self.send_message_to_user(
+                    matrix_user,
+                    &report.message(),
+                    &report.formatted_message(),
+                )
+                .await?
+            }
+            None => {
+                Matrix::send_message(self, &report.message(), &report.formatted_message())
+                    .await?
+            }
+        }
+        Ok(())
+    }
+}
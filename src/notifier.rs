@@ -0,0 +1,179 @@
+// The MIT License (MIT)
+// Copyright © 2021 Aukbit Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::errors::ScoutyError;
+use crate::report::Report;
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    static ref TAG: Regex =
+        Regex::new(r"<(/?)([a-zA-Z][a-zA-Z0-9-]*)((?:\s[^<>]*)?)/?>").unwrap();
+    static ref HREF: Regex = Regex::new(r#"href\s*=\s*["']([^"']*)["']"#).unwrap();
+}
+
+/// Backend to which scouty messages and reports are sent (e.g. Matrix or Telegram)
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    /// Returns the notifier name, as shown in logs
+    fn name(&self) -> &'static str;
+
+    /// Send a message, as plain text and as HTML, to the default destination
+    async fn send_message(
+        &self,
+        message: &str,
+        formatted_message: &str,
+    ) -> Result<(), ScoutyError>;
+
+    /// Send a report, optionally routed to the matrix user defined for the stashes in it.
+    /// Notifiers without per stash destinations send every report to the default one.
+    async fn send_report(
+        &self,
+        report: &Report,
+        _matrix_user: Option<&str>,
+    ) -> Result<(), ScoutyError> {
+        self.send_message(&report.message(), &report.formatted_message())
+            .await
+    }
+}
+
+/// Split a message by lines in chunks of at most max characters
+pub fn split_message(text: &str, max: usize) -> Vec<String> {
+    let mut chunks: Vec<String> = Vec::new();
+    let mut chunk = String::new();
+    // NOTE: lines longer than max are split as well
+    let lines = text.lines().flat_map(|line| {
+        let chars: Vec<char> = line.chars().collect();
+        chars
+            .chunks(max.max(1))
+            .map(|c| c.iter().collect::<String>())
+            .collect::<Vec<String>>()
+    });
+    for line in lines {
+        if !chunk.is_empty() && chunk.chars().count() + line.chars().count() + 1 > max {
+            chunks.push(std::mem::take(&mut chunk));
+        }
+        if !chunk.is_empty() {
+            chunk.push('\n');
+        }
+        chunk.push_str(&line);
+    }
+    if !chunk.is_empty() {
+        chunks.push(chunk);
+    }
+    chunks
+}
+
+/// Piece of the HTML subset used in reports
+#[derive(Debug, PartialEq)]
+pub enum Html<'a> {
+    /// Text between tags, still escaped
+    Text(&'a str),
+    Tag(Tag<'a>),
+}
+
+/// Opening or closing tag, with its name in lowercase
+#[derive(Debug, PartialEq)]
+pub struct Tag<'a> {
+    pub name: String,
+    pub is_closing: bool,
+    attributes: &'a str,
+}
+
+impl<'a> Tag<'a> {
+    /// Returns the href attribute of the tag, still escaped
+    pub fn href(&self) -> Option<&'a str> {
+        HREF.captures(self.attributes)
+            .and_then(|caps| caps.get(1))
+            .map(|href| href.as_str())
+    }
+}
+
+/// Split the HTML of a report into text and tags, so that each notifier
+/// can translate it into its own markup
+pub fn parse_html(html: &str) -> Vec<Html<'_>> {
+    let mut nodes = Vec::new();
+    let mut last = 0;
+    for caps in TAG.captures_iter(html) {
+        let tag = caps.get(0).unwrap();
+        if tag.start() > last {
+            nodes.push(Html::Text(&html[last..tag.start()]));
+        }
+        last = tag.end();
+        nodes.push(Html::Tag(Tag {
+            name: caps[2].to_lowercase(),
+            is_closing: &caps[1] == "/",
+            attributes: caps.get(3).map_or("", |a| a.as_str()),
+        }));
+    }
+    if last < html.len() {
+        nodes.push(Html::Text(&html[last..]));
+    }
+    nodes
+}
+
+/// Remove every tag, keeping the text escaped
+pub fn strip_tags(html: &str) -> String {
+    TAG.replace_all(html, "").to_string()
+}
+
+/// Unescape the HTML entities used in reports
+pub fn unescape_text(text: &str) -> String {
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_splits_long_messages_by_lines() {
+        assert_eq!(
+            split_message("first line\nsecond line\nthird", 22),
+            vec!["first line\nsecond line", "third"]
+        );
+        assert_eq!(split_message("abcdef", 4), vec!["abcd", "ef"]);
+    }
+
+    #[test]
+    fn it_parses_report_html() {
+        let nodes = parse_html(
+            "<b>cpu &lt; 80%</b><br/><a href=\"https://x.io?a=1&amp;b=2\">x</a>",
+        );
+        assert_eq!(nodes.len(), 7);
+        assert_eq!(nodes[1], Html::Text("cpu &lt; 80%"));
+        match &nodes[4] {
+            Html::Tag(tag) => {
+                assert_eq!(tag.name, "a");
+                assert!(!tag.is_closing);
+                assert_eq!(tag.href(), Some("https://x.io?a=1&amp;b=2"));
+            }
+            _ => panic!("expected a tag"),
+        }
+        assert_eq!(strip_tags("<i>cpu &amp; mem</i>"), "cpu &amp; mem");
+        assert_eq!(unescape_text("cpu&nbsp;&lt;&amp;"), "cpu <&");
+    }
+}
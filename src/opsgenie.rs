@@ -0,0 +1,125 @@
+// The MIT License (MIT)
+// Copyright © 2021 Aukbit Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::config::CONFIG;
+use crate::errors::IncidentError;
+use crate::incidents::{dispatch_event, Condition, Incident, IncidentBackend};
+use async_trait::async_trait;
+use log::info;
+use serde::Serialize;
+use std::{collections::BTreeMap, result::Result};
+
+/// Maximum number of characters of an alert message
+const OPSGENIE_MAX_MESSAGE_LENGTH: usize = 130;
+
+#[derive(Serialize, Debug)]
+struct CreateAlert<'a> {
+    message: String,
+    alias: &'a str,
+    description: String,
+    priority: &'a str,
+    source: &'a str,
+    tags: Vec<&'a str>,
+    details: BTreeMap<&'a str, String>,
+}
+
+#[derive(Serialize, Debug)]
+struct CloseAlert<'a> {
+    source: &'a str,
+    note: &'a str,
+}
+
+/// Opsgenie Alerts API backend
+pub struct Opsgenie {
+    client: reqwest::Client,
+    api_url: String,
+    api_key: String,
+}
+
+impl Opsgenie {
+    /// Returns an Opsgenie backend if an API key is defined
+    pub fn new() -> Option<Opsgenie> {
+        let config = CONFIG.clone();
+        if config.opsgenie_api_key.is_empty() {
+            return None;
+        }
+        info!("Incidents will be sent to Opsgenie");
+        Some(Opsgenie {
+            client: reqwest::Client::new(),
+            api_url: config.opsgenie_api_url.trim_end_matches('/').to_string(),
+            api_key: config.opsgenie_api_key,
+        })
+    }
+
+    fn post(&self, path: &str) -> reqwest::RequestBuilder {
+        self.client
+            .post(format!("{}{}", self.api_url, path))
+            .header("Authorization", format!("GenieKey {}", self.api_key))
+    }
+}
+
+#[async_trait]
+impl IncidentBackend for Opsgenie {
+    fn name(&self) -> &'static str {
+        "Opsgenie"
+    }
+
+    async fn trigger(&self, incident: &Incident) -> Result<(), IncidentError> {
+        let priority = match incident.condition {
+            Condition::Slashed => "P1",
+            Condition::Chilled | Condition::Offline => "P2",
+            Condition::StartsInactiveNextEra => "P3",
+        };
+        let message = format!(
+            "{} {} {}",
+            incident.chain, incident.name, incident.condition
+        );
+        let mut details = BTreeMap::new();
+        details.insert("stash", incident.stash.to_string());
+        details.insert("url", incident.url.clone());
+        if !incident.details.is_empty() {
+            details.insert("signals", incident.details.join(", "));
+        }
+        let alert = CreateAlert {
+            message: message.chars().take(OPSGENIE_MAX_MESSAGE_LENGTH).collect(),
+            alias: &incident.dedup_key,
+            description: format!("{}\n{}", incident.summary(), incident.url),
+            priority,
+            source: "scouty",
+            tags: vec!["scouty", incident.condition.key()],
+            details,
+        };
+        dispatch_event(self.post("/v2/alerts").json(&alert)).await
+    }
+
+    async fn resolve(&self, incident: &Incident) -> Result<(), IncidentError> {
+        let close = CloseAlert {
+            source: "scouty",
+            note: "Condition cleared",
+        };
+        dispatch_event(
+            self.post(&format!("/v2/alerts/{}/close", incident.dedup_key))
+                .query(&[("identifierType", "alias")])
+                .json(&close),
+        )
+        .await
+    }
+}
@@ -0,0 +1,129 @@
+// The MIT License (MIT)
+// Copyright © 2021 Aukbit Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::config::CONFIG;
+use crate::errors::IncidentError;
+use crate::incidents::{dispatch_event, Condition, Incident, IncidentBackend};
+use async_trait::async_trait;
+use log::info;
+use serde::Serialize;
+use std::result::Result;
+
+const PAGERDUTY_EVENTS_URL: &str = "https://events.pagerduty.com/v2/enqueue";
+
+#[derive(Serialize, Debug)]
+struct Link<'a> {
+    href: &'a str,
+    text: &'a str,
+}
+
+#[derive(Serialize, Debug)]
+struct Payload<'a> {
+    summary: String,
+    source: &'a str,
+    severity: &'a str,
+    component: String,
+    group: &'a str,
+    class: &'a str,
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    custom_details: &'a [String],
+}
+
+#[derive(Serialize, Debug)]
+struct Event<'a> {
+    routing_key: &'a str,
+    event_action: &'a str,
+    dedup_key: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    payload: Option<Payload<'a>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    links: Vec<Link<'a>>,
+}
+
+/// PagerDuty Events API v2 backend
+pub struct PagerDuty {
+    client: reqwest::Client,
+    routing_key: String,
+}
+
+impl PagerDuty {
+    /// Returns a PagerDuty backend if an integration routing key is defined
+    pub fn new() -> Option<PagerDuty> {
+        let config = CONFIG.clone();
+        if config.pagerduty_routing_key.is_empty() {
+            return None;
+        }
+        info!("Incidents will be sent to PagerDuty");
+        Some(PagerDuty {
+            client: reqwest::Client::new(),
+            routing_key: config.pagerduty_routing_key,
+        })
+    }
+
+    async fn send_event(&self, event: &Event<'_>) -> Result<(), IncidentError> {
+        dispatch_event(self.client.post(PAGERDUTY_EVENTS_URL).json(event)).await
+    }
+}
+
+#[async_trait]
+impl IncidentBackend for PagerDuty {
+    fn name(&self) -> &'static str {
+        "PagerDuty"
+    }
+
+    async fn trigger(&self, incident: &Incident) -> Result<(), IncidentError> {
+        let severity = match incident.condition {
+            Condition::Slashed => "critical",
+            Condition::Chilled | Condition::Offline => "error",
+            Condition::StartsInactiveNextEra => "warning",
+        };
+        self.send_event(&Event {
+            routing_key: &self.routing_key,
+            event_action: "trigger",
+            dedup_key: &incident.dedup_key,
+            payload: Some(Payload {
+                summary: incident.summary(),
+                source: "scouty",
+                severity,
+                component: incident.stash.to_string(),
+                group: &incident.chain,
+                class: incident.condition.key(),
+                custom_details: &incident.details,
+            }),
+            links: vec![Link {
+                href: &incident.url,
+                text: "Subscan",
+            }],
+        })
+        .await
+    }
+
+    async fn resolve(&self, incident: &Incident) -> Result<(), IncidentError> {
+        self.send_event(&Event {
+            routing_key: &self.routing_key,
+            event_action: "resolve",
+            dedup_key: &incident.dedup_key,
+            payload: None,
+            links: Vec::new(),
+        })
+        .await
+    }
+}
@@ -25,11 +25,13 @@ use log::debug;
 use std::{collections::BTreeMap, convert::TryInto, result::Result, str::FromStr};
 use subxt::utils::AccountId32;
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct ParaRecords {
     current_session_index: u32,
     config_stashes: Vec<(AccountId32, u32)>,
+    active_validator_indices: Vec<u32>,
     pub records: BTreeMap<String, bool>,
+    pub bitfields: BTreeMap<String, u32>,
 }
 
 impl ParaRecords {
@@ -37,7 +39,9 @@ impl ParaRecords {
         Self {
             current_session_index: 0,
             config_stashes: vec![],
+            active_validator_indices: vec![],
             records: BTreeMap::new(),
+            bitfields: BTreeMap::new(),
         }
     }
 
@@ -87,13 +91,32 @@ impl ParaRecords {
                     let session_index = self.current_session_index - 7;
                     let key = format!("{}:{}", session_index, stash);
                     self.records.remove(&key);
+                    self.bitfields.remove(&key);
                 }
             }
+            self.active_validator_indices = active_validator_indices;
             self.set_session(new_session_index);
         }
         debug!("records {:?}", self.records);
     }
 
+    /// Count the availability bitfields signed by config stashes in the current session.
+    /// Note: `bitfield_indices` are indices into the para validators set
+    pub fn insert_bitfields(&mut self, bitfield_indices: Vec<u32>) {
+        for (stash, index) in self.config_stashes.iter() {
+            if let Some(para_index) = self
+                .active_validator_indices
+                .iter()
+                .position(|x| x == index)
+            {
+                if bitfield_indices.contains(&(para_index as u32)) {
+                    let key = format!("{}:{}", self.current_session_index, stash);
+                    *self.bitfields.entry(key).or_insert(0) += 1;
+                }
+            }
+        }
+    }
+
     pub fn is_para_validator(&self, stash: &AccountId32) -> bool {
         let key = format!("{}:{}", self.current_session_index, stash);
         match self.records.get(&key) {
@@ -102,10 +125,32 @@ impl ParaRecords {
         }
     }
 
+    /// Returns whether the stash was a para validator in the previous session, if known
+    pub fn was_para_validator(&self, stash: &AccountId32) -> Option<bool> {
+        let previous_session_index = self.current_session_index.checked_sub(1)?;
+        let key = format!("{}:{}", previous_session_index, stash);
+        self.records.get(&key).copied()
+    }
+
+    pub fn previous_session_bitfields(&self, stash: &AccountId32) -> u32 {
+        let previous_session_index = match self.current_session_index.checked_sub(1) {
+            Some(index) => index,
+            None => return 0,
+        };
+        let key = format!("{}:{}", previous_session_index, stash);
+        match self.bitfields.get(&key) {
+            Some(total) => *total,
+            None => 0,
+        }
+    }
+
     pub fn previous_six_sessions_total(&self, stash: &AccountId32) -> u32 {
         let mut total: u32 = 0;
         for n in 1..=6 {
-            let session_index = self.current_session_index - n;
+            let session_index = match self.current_session_index.checked_sub(n) {
+                Some(index) => index,
+                None => break,
+            };
             let key = format!("{}:{}", session_index, stash);
             total += match self.records.get(&key) {
                 Some(true) => 1,
@@ -113,6 +158,6 @@ impl ParaRecords {
                 None => 0,
             };
         }
-        return total;
+        total
     }
 }
@@ -20,28 +20,29 @@
 // SOFTWARE.
 use crate::config::CONFIG;
 use crate::errors::ScoutyError;
-use crate::hooks::Hook;
+use crate::hooks::{Hook, Severity};
 use log::info;
 use serde::Deserialize;
 use std::{convert::TryInto, result::Result};
 use subxt::{backend::legacy::LegacyRpcMethods, utils::AccountId32, PolkadotConfig};
 
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct Init {
     pub block_number: u32,
-    pub now: u64,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct Network {
     pub name: String,
     pub token_symbol: String,
     pub token_decimals: u8,
+    pub endpoint: String,
 }
 
 impl Network {
     pub async fn load(
         rpc: &LegacyRpcMethods<PolkadotConfig>,
+        endpoint: &str,
     ) -> Result<Network, ScoutyError> {
         let properties = rpc.system_properties().await?;
 
@@ -67,6 +68,7 @@ impl Network {
             name: chain_name,
             token_symbol,
             token_decimals,
+            endpoint: endpoint_host(endpoint),
         })
     }
 }
@@ -75,16 +77,23 @@ impl Network {
 pub struct Points {
     pub validator: u32,
     pub era_avg: f64,
-    pub ci99_9_interval: (f64, f64),
-    pub outlier_limits: (f64, f64),
 }
 
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Clone, Deserialize, Default)]
 pub struct Session {
     pub active_era_index: u32,
     pub current_session_index: u32,
     pub eras_session_index: u32,
     pub queued_session_keys_changed: bool,
+    #[serde(default)]
+    pub sessions_per_era: u32,
+}
+
+impl Session {
+    /// Returns true if the session is the last one of the active era
+    pub fn is_last_session_of_era(&self) -> bool {
+        self.eras_session_index == self.sessions_per_era
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -93,6 +102,10 @@ pub struct Validator {
     #[serde(default)]
     pub name: String,
     #[serde(default)]
+    pub label: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
     pub is_active: bool,
     #[serde(default)]
     pub is_queued: bool,
@@ -105,6 +118,8 @@ pub struct Validator {
     #[serde(default)]
     pub is_offline: bool,
     #[serde(default)]
+    pub offline_signals: Vec<String>,
+    #[serde(default)]
     pub hooks: Vec<Hook>,
 }
 
@@ -113,15 +128,27 @@ impl Validator {
         Self {
             stash,
             name: "".to_string(),
+            label: "".to_string(),
+            tags: Vec::new(),
             is_active: false,
             is_queued: false,
             queued_session_keys: Vec::new(),
             is_slashed: false,
             is_chilled: false,
             is_offline: false,
+            offline_signals: Vec::new(),
             hooks: Vec::new(),
         }
     }
+
+    /// Returns the label defined for the stash followed by the identity, or only the identity
+    pub fn display_name(&self) -> String {
+        if self.label.is_empty() {
+            self.name.to_string()
+        } else {
+            format!("{} ({})", self.label, self.name)
+        }
+    }
 }
 
 pub type Validators = Vec<Validator>;
@@ -133,7 +160,33 @@ pub struct Referendum {
     #[serde(default)]
     pub track: u16,
     #[serde(default)]
-    pub hook: Hook,
+    pub hooks: Vec<Hook>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct ChainEvent {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub block_number: u32,
+    #[serde(default)]
+    pub fields: serde_json::Value,
+    #[serde(default)]
+    pub hooks: Vec<Hook>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct RuntimeUpgrade {
+    #[serde(default)]
+    pub block_number: u32,
+    #[serde(default)]
+    pub old_spec_version: u32,
+    #[serde(default)]
+    pub new_spec_version: u32,
+    #[serde(default)]
+    pub incompatibilities: Vec<String>,
+    #[serde(default)]
+    pub hooks: Vec<Hook>,
 }
 
 #[derive(Debug, Deserialize, Default)]
@@ -142,22 +195,36 @@ pub struct Slash {
     #[serde(default)]
     pub amount_value: u128,
     #[serde(default)]
-    pub hook: Hook,
+    pub hooks: Vec<Hook>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub enum Section {
     Init,
+    #[default]
     Session,
     Slash,
     Chill,
     Offline,
     Referenda,
+    RuntimeUpgrade,
+    Events,
+    Scheduled,
 }
 
-impl Default for Section {
-    fn default() -> Self {
-        Section::Session
+impl std::fmt::Display for Section {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Section::Init => write!(f, "Init"),
+            Section::Session => write!(f, "Session"),
+            Section::Slash => write!(f, "Slash"),
+            Section::Chill => write!(f, "Chill"),
+            Section::Offline => write!(f, "Offline"),
+            Section::Referenda => write!(f, "Referenda"),
+            Section::RuntimeUpgrade => write!(f, "Runtime upgrade"),
+            Section::Events => write!(f, "Chain events"),
+            Section::Scheduled => write!(f, "Scheduled"),
+        }
     }
 }
 
@@ -167,16 +234,144 @@ pub struct RawData {
     pub network: Network,
     pub validators: Validators,
     pub session: Session,
-    pub referendum: Referendum,
-    pub slash: Slash,
+    pub referenda: Vec<Referendum>,
+    pub events: Vec<ChainEvent>,
+    pub runtime_upgrade: RuntimeUpgrade,
+    pub slashes: Vec<Slash>,
     pub section: Section,
 }
 
+impl RawData {
+    /// Split the data by the matrix user to which each validator stash notification is sent,
+    /// validators without a particular matrix user and any other data are kept in the default one (None)
+    pub fn route(mut self) -> Vec<(Option<String>, RawData)> {
+        let config = CONFIG.clone();
+        let mut routes: Vec<(Option<String>, RawData)> = Vec::new();
+
+        for v in std::mem::take(&mut self.validators) {
+            let matrix_user = config.for_stash(&v.stash).matrix_user;
+            if matrix_user == config.matrix_user {
+                self.validators.push(v);
+                continue;
+            }
+            match routes
+                .iter_mut()
+                .find(|(user, _)| user.as_ref() == Some(&matrix_user))
+            {
+                Some((_, data)) => data.validators.push(v),
+                None => {
+                    let mut data = RawData {
+                        init: self.init.clone(),
+                        network: self.network.clone(),
+                        session: self.session.clone(),
+                        section: self.section,
+                        ..Default::default()
+                    };
+                    data.validators.push(v);
+                    routes.push((Some(matrix_user), data));
+                }
+            }
+        }
+
+        // Slashes follow the validator slashed
+        for slash in std::mem::take(&mut self.slashes) {
+            match routes.iter_mut().find(|(_, data)| {
+                data.validators
+                    .iter()
+                    .any(|v| slash.who.as_ref() == Some(&v.stash))
+            }) {
+                Some((_, data)) => data.slashes.push(slash),
+                None => self.slashes.push(slash),
+            }
+        }
+
+        routes.insert(0, (None, self));
+        routes.retain(|(_, data)| data.has_content());
+        routes
+    }
+
+    /// Returns every hook executed
+    pub fn hooks(&self) -> Vec<&Hook> {
+        self.validators
+            .iter()
+            .flat_map(|v| v.hooks.iter())
+            .chain(self.slashes.iter().flat_map(|slash| slash.hooks.iter()))
+            .chain(self.referenda.iter().flat_map(|r| r.hooks.iter()))
+            .chain(self.events.iter().flat_map(|e| e.hooks.iter()))
+            .chain(self.runtime_upgrade.hooks.iter())
+            .collect()
+    }
+
+    /// Returns true if there is anything to be reported for the section
+    fn has_content(&self) -> bool {
+        match self.section {
+            Section::Init | Section::Session | Section::Scheduled => {
+                !self.validators.is_empty()
+            }
+            Section::Slash => !self.slashes.is_empty(),
+            Section::Chill => self.validators.iter().any(|v| v.is_chilled),
+            Section::Offline => self.validators.iter().any(|v| v.is_offline),
+            Section::Referenda => !self.referenda.is_empty(),
+            Section::RuntimeUpgrade => true,
+            Section::Events => !self.events.is_empty(),
+        }
+    }
+}
+
+/// Validator status, as shown by the notifiers that render a card per validator
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Status {
+    Active,
+    Inactive,
+    Slashed,
+    Chilled,
+    Offline,
+}
+
+impl From<&Validator> for Status {
+    fn from(validator: &Validator) -> Status {
+        if validator.is_slashed {
+            Status::Slashed
+        } else if validator.is_chilled {
+            Status::Chilled
+        } else if validator.is_offline {
+            Status::Offline
+        } else if validator.is_active {
+            Status::Active
+        } else {
+            Status::Inactive
+        }
+    }
+}
+
+/// Lines of a report about a single validator, with the warning and critical
+/// hook output lines kept apart as alerts
+#[derive(Debug, Clone)]
+pub struct Card {
+    pub stash: AccountId32,
+    pub title: String,
+    pub url: String,
+    pub status: Status,
+    pub lines: Body,
+    pub alerts: Vec<(Severity, String)>,
+}
+
 type Body = Vec<String>;
 
 pub struct Report {
     body: Body,
+    // NOTE: summary and cards hold the same lines as body, split by validator
+    summary: Body,
+    cards: Vec<Card>,
+    is_card_open: bool,
+    // NOTE: chain, era and section are only known for reports built from raw data
+    chain: String,
+    era: Option<u32>,
+    section: Option<Section>,
     is_short: bool,
+    severity: Severity,
+    // NOTE: None until a hook is added, then true only while every hook asked to suppress
+    suppress: Option<bool>,
 }
 
 impl Report {
@@ -184,11 +379,23 @@ impl Report {
         let config = CONFIG.clone();
         Report {
             body: Vec::new(),
+            summary: Vec::new(),
+            cards: Vec::new(),
+            is_card_open: false,
+            chain: String::new(),
+            era: None,
+            section: None,
             is_short: config.is_short,
+            severity: Severity::default(),
+            suppress: None,
         }
     }
 
     pub fn add_raw_text(&mut self, t: String) {
+        match self.cards.last_mut() {
+            Some(card) if self.is_card_open => card.lines.push(t.clone()),
+            _ => self.summary.push(t.clone()),
+        }
         self.body.push(t);
     }
 
@@ -201,12 +408,165 @@ impl Report {
         self.add_raw_text("".into());
     }
 
+    /// Add the validator name linked to subscan and open a card for the lines that follow
+    pub fn add_validator(&mut self, validator: &Validator, network: &Network) {
+        let is_active_desc = if validator.is_active { "🟢" } else { "🔴" };
+        let url = format!(
+            "https://{}.subscan.io/validator/{}",
+            network.name.to_lowercase(),
+            validator.stash
+        );
+        self.body.push(format!(
+            "{} <b><a href=\"{}\">{}</a></b>",
+            is_active_desc,
+            url,
+            validator.display_name(),
+        ));
+        self.cards.push(Card {
+            stash: validator.stash.clone(),
+            title: validator.display_name(),
+            url,
+            status: validator.into(),
+            lines: Vec::new(),
+            alerts: Vec::new(),
+        });
+        self.is_card_open = true;
+    }
+
+    /// Close the validator card, the lines that follow are part of the summary
+    pub fn close_validator(&mut self) {
+        self.is_card_open = false;
+    }
+
+    /// Returns the name of the chain the report is about
+    pub fn chain(&self) -> &str {
+        &self.chain
+    }
+
+    /// Returns the active era index, if known when the report was built
+    pub fn era(&self) -> Option<u32> {
+        self.era
+    }
+
+    /// Returns the section of the report
+    pub fn section(&self) -> Option<Section> {
+        self.section
+    }
+
+    /// Returns the highest severity raised by any hook
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    /// Returns true if every hook asked for the notification to be suppressed,
+    /// critical notifications are never suppressed
+    pub fn is_suppressed(&self) -> bool {
+        self.suppress == Some(true) && self.severity < Severity::Critical
+    }
+
+    /// Add the hook filename followed by the stdout lines and metrics of the hook output protocol
+    pub fn add_hook(&mut self, hook: Hook) {
+        let exists_desc = if !hook.filename_exists { "❌" } else { "" };
+        self.add_text(format!("🪝 <code>{}</code> {}", hook.filename, exists_desc));
+
+        if hook.has_failed() {
+            let failure_desc = match (&hook.error, hook.exit_code) {
+                (Some(error), _) => error.to_string(),
+                (None, Some(exit_code)) => format!("exit code {}", exit_code),
+                (None, None) => "no exit code".to_string(),
+            };
+            let retries_desc = match hook.retries {
+                0 => String::new(),
+                1 => " (1 retry)".to_string(),
+                retries => format!(" ({} retries)", retries),
+            };
+            self.add_raw_text(format!(
+                "‣ ❗ Failed with {} after {:.2}s{}",
+                escape_html(&failure_desc),
+                hook.duration.as_secs_f64(),
+                retries_desc
+            ));
+            // show only the last lines of stderr
+            let stderr = String::from_utf8_lossy(&hook.stderr);
+            let lines: Vec<&str> =
+                stderr.lines().filter(|line| !line.is_empty()).collect();
+            for line in lines.iter().skip(lines.len().saturating_sub(3)) {
+                self.add_raw_text(format!("‣ <code>{}</code>", escape_html(line)));
+            }
+        }
+
+        if hook.timed_out {
+            self.add_raw_text(format!(
+                "‣ ⏱️ Timed out after {:.0} seconds and was killed",
+                hook.duration.as_secs_f64()
+            ));
+        }
+
+        if hook.stdout_truncated {
+            let config = CONFIG.clone();
+            self.add_raw_text(format!(
+                "‣ ✂️ Output truncated after {} bytes",
+                config.hook_max_stdout_bytes
+            ));
+        }
+
+        let output = hook.output();
+        self.severity = self.severity.max(output.severity);
+        self.suppress = Some(self.suppress.unwrap_or(true) && output.suppress);
+        for (severity, line) in output.lines {
+            let text = match severity {
+                Severity::Info => {
+                    self.add_raw_text(format!("‣ {}", line));
+                    continue;
+                }
+                Severity::Warning => format!("‣ ⚠️ {}", line),
+                Severity::Critical => format!("‣ 🚨 {}", line),
+            };
+            match self.cards.last_mut() {
+                Some(card) if self.is_card_open => {
+                    card.alerts.push((severity, line));
+                    self.body.push(text);
+                }
+                _ => self.add_raw_text(text),
+            }
+        }
+        for (name, value) in output.metrics {
+            self.add_text(format!("‣ 📈 {} = {}", name, value));
+        }
+    }
+
+    /// Returns the lines prefixed by the severity when raised by any hook
+    fn with_severity(&self, body: &Body) -> Vec<String> {
+        let header = match self.severity {
+            Severity::Info => return body.clone(),
+            Severity::Warning => format!("⚠️ <b>{}</b>", self.severity),
+            Severity::Critical => format!("🚨 <b>{}</b>", self.severity),
+        };
+        let mut lines = vec![header];
+        lines.extend(body.iter().cloned());
+        lines
+    }
+
+    fn lines(&self) -> Vec<String> {
+        self.with_severity(&self.body)
+    }
+
+    /// Returns the report lines that are not part of any validator card
+    pub fn summary(&self) -> Vec<String> {
+        self.with_severity(&self.summary)
+    }
+
+    /// Returns a card for each validator in the report
+    pub fn cards(&self) -> &[Card] {
+        &self.cards
+    }
+
     pub fn message(&self) -> String {
-        self.body.join("\n")
+        self.lines().join("\n")
     }
 
     pub fn formatted_message(&self) -> String {
-        self.body.join("<br/>")
+        self.lines().join("<br/>")
     }
 
     pub fn log(&self) {
@@ -222,6 +582,9 @@ impl From<RawData> for Report {
     /// Converts a Scouty `RawData` into a [`Report`].
     fn from(data: RawData) -> Report {
         let mut report = Report::new();
+        report.chain = data.network.name.clone();
+        report.era = Some(data.session.active_era_index).filter(|era| *era > 0);
+        report.section = Some(data.section);
 
         // Scouty package
         report.add_raw_text(format!(
@@ -230,6 +593,11 @@ impl From<RawData> for Report {
             env!("CARGO_PKG_VERSION")
         ));
 
+        // Active endpoint
+        if !data.network.endpoint.is_empty() {
+            report.add_text(format!("📡 <code>{}</code>", data.network.endpoint));
+        }
+
         // --- Specific report section here [START] -->
 
         match data.section {
@@ -239,14 +607,18 @@ impl From<RawData> for Report {
             Section::Slash => section_slash(&mut report, data),
             Section::Chill => section_chill(&mut report, data),
             Section::Offline => section_offline(&mut report, data),
+            Section::RuntimeUpgrade => section_runtime_upgrade(&mut report, data),
+            Section::Events => section_events(&mut report, data),
+            Section::Scheduled => section_scheduled(&mut report, data),
         };
 
         // --- Specific report section here [END] ---|
 
-        report.add_break();
-
-        report.add_raw_text("___".into());
-        report.add_break();
+        // NOTE: the separator is not part of the summary, notifiers rendering
+        // cards have their own way to separate reports
+        report.body.push("".into());
+        report.body.push("___".into());
+        report.body.push("".into());
 
         // Log report
         report.log();
@@ -259,28 +631,17 @@ fn sub_section_validators(report: &mut Report, data: RawData) -> &Report {
     // Validators info
     for validator in data.validators {
         report.add_break();
-
-        let is_active_desc = if validator.is_active { "🟢" } else { "🔴" };
-        report.add_raw_text(format!(
-            "{} <b><a href=\"https://{}.subscan.io/validator/{}\">{}</a></b>",
-            is_active_desc,
-            data.network.name.to_lowercase(),
-            validator.stash,
-            validator.name,
-        ));
+        report.add_validator(&validator, &data.network);
+        if !validator.tags.is_empty() {
+            report.add_text(format!("🏷️ {}", validator.tags.join(", ")));
+        }
         for (i, hook) in validator.hooks.into_iter().enumerate() {
             if i != 0 {
                 report.add_break();
             }
-            let exists_desc = if !hook.filename_exists { "❌" } else { "" };
-            report.add_text(format!("🪝 <code>{}</code> {}", hook.filename, exists_desc));
-
-            let raw_output = String::from_utf8(hook.stdout).unwrap();
-            // filter lines that start by special character '!'
-            for line in raw_output.lines().filter(|line| line.starts_with("!")) {
-                report.add_raw_text(format!("‣ {}", line.strip_prefix("!").unwrap()));
-            }
+            report.add_hook(hook);
         }
+        report.close_validator();
     }
     report
 }
@@ -304,8 +665,8 @@ fn section_session(report: &mut Report, data: RawData) -> &Report {
     report.add_raw_text(format!(
         "⛓️ <b>{}</b> -> {} {} session ({}) of era <b>{}</b>",
         data.network.name,
-        session_flag(data.session.eras_session_index),
-        session_ordinal_number(data.session.eras_session_index),
+        session_flag(&data.session),
+        session_ordinal_number(&data.session),
         data.session.current_session_index,
         data.session.active_era_index
     ));
@@ -313,46 +674,79 @@ fn section_session(report: &mut Report, data: RawData) -> &Report {
     sub_section_validators(report, data)
 }
 
-fn section_referenda(report: &mut Report, data: RawData) -> &Report {
+fn section_scheduled(report: &mut Report, data: RawData) -> &Report {
     // Network info
     report.add_break();
     report.add_raw_text(format!(
-        "⛓️ <b>{}</b> -> 🗳️ Referendum {} ({}) has been submitted.",
-        data.network.name, data.referendum.index, data.referendum.track,
-    ));
-
-    report.add_break();
-    report.add_raw_text(format!(
-        "Vote here -> <a href=\"https://polkadot.js.org/apps/?rpc=wss%3A%2F%2Frpc.ibp.network%2F{}#/referenda\">Polkadot.js</a>",
-        data.network.name.to_lowercase()
-    ));
-    report.add_raw_text(format!(
-        "Or here -> <a href=\"https://{}.polkassembly.io/referenda/{}\">Polkassembly</a>",
-        data.network.name.to_lowercase(),
-        data.referendum.index
-    ));
-    report.add_raw_text(format!(
-        "Or here -> <a href=\"https://{}.subsquare.io/referenda/{}\">SubSquare</a>",
+        "⛓️ <b>{}</b> -> ⏰ Scheduled check at block <a href=\"https://{}.subscan.io/block/{}\">#{}</a>, session {} of era <b>{}</b>",
+        data.network.name,
         data.network.name.to_lowercase(),
-        data.referendum.index
+        data.init.block_number,
+        data.init.block_number,
+        data.session.current_session_index,
+        data.session.active_era_index
     ));
 
-    // Hook
-    report.add_break();
-    let exists_desc = if !data.referendum.hook.filename_exists {
-        "❌"
-    } else {
-        ""
-    };
-    report.add_text(format!(
-        "🪝 <code>{}</code> {}",
-        data.referendum.hook.filename, exists_desc
-    ));
+    sub_section_validators(report, data)
+}
+
+fn section_referenda(report: &mut Report, data: RawData) -> &Report {
+    for referendum in data.referenda {
+        // Network info
+        report.add_break();
+        report.add_raw_text(format!(
+            "⛓️ <b>{}</b> -> 🗳️ Referendum {} ({}) has been submitted.",
+            data.network.name, referendum.index, referendum.track,
+        ));
+
+        report.add_break();
+        report.add_raw_text(format!(
+            "Vote here -> <a href=\"https://polkadot.js.org/apps/?rpc=wss%3A%2F%2Frpc.ibp.network%2F{}#/referenda\">Polkadot.js</a>",
+            data.network.name.to_lowercase()
+        ));
+        report.add_raw_text(format!(
+            "Or here -> <a href=\"https://{}.polkassembly.io/referenda/{}\">Polkassembly</a>",
+            data.network.name.to_lowercase(),
+            referendum.index
+        ));
+        report.add_raw_text(format!(
+            "Or here -> <a href=\"https://{}.subsquare.io/referenda/{}\">SubSquare</a>",
+            data.network.name.to_lowercase(),
+            referendum.index
+        ));
+
+        // Hooks
+        for hook in referendum.hooks {
+            report.add_break();
+            report.add_hook(hook);
+        }
+    }
+
+    report
+}
+
+fn section_events(report: &mut Report, data: RawData) -> &Report {
+    for event in data.events {
+        // Network info
+        report.add_break();
+        report.add_raw_text(format!(
+            "⛓️ <b>{}</b> -> 📣 <code>{}</code> at block <a href=\"https://{}.subscan.io/block/{}\">#{}</a>",
+            data.network.name,
+            event.name,
+            data.network.name.to_lowercase(),
+            event.block_number,
+            event.block_number,
+        ));
+        report.add_raw_text(format!(
+            "‣ <code>{}</code>",
+            escape_html(&event.fields.to_string())
+        ));
 
-    let raw_output = String::from_utf8(data.referendum.hook.stdout).unwrap();
-    // filter lines that start by special character '!'
-    for line in raw_output.lines().filter(|line| line.starts_with("!")) {
-        report.add_raw_text(format!("‣ {}", line.strip_prefix("!").unwrap()));
+        // Hooks
+        for hook in event.hooks {
+            report.add_break();
+            report.add_hook(hook);
+        }
     }
 
     report
@@ -361,55 +755,50 @@ fn section_referenda(report: &mut Report, data: RawData) -> &Report {
 fn section_slash(report: &mut Report, data: RawData) -> &Report {
     // Network info
     report.add_break();
+    let slashes_desc = if data.slashes.len() > 1 {
+        format!("🏴‍☠️ {} slashes occurred!", data.slashes.len())
+    } else {
+        "🏴‍☠️ Slash occurred!".to_string()
+    };
     report.add_raw_text(format!(
-        "⛓️ <b>{}</b> -> <a href=\"https://polkadot.js.org/apps/?rpc=wss%3A%2F%2F{}.api.onfinality.io%2Fpublic-ws#/staking/slashes\">🏴‍☠️ Slash occurred!</a>",
+        "⛓️ <b>{}</b> -> <a href=\"https://polkadot.js.org/apps/?rpc=wss%3A%2F%2F{}.api.onfinality.io%2Fpublic-ws#/staking/slashes\">{}</a>",
         data.network.name,
         data.network.name.to_lowercase(),
+        slashes_desc
     ));
 
-    let slashed_amount = format!(
-        "{:.4} {}",
-        (data.slash.amount_value) as f64 / 10f64.powi(data.network.token_decimals.into()),
-        data.network.token_symbol
-    );
-
     // Validators info
     for validator in data.validators {
         if validator.is_slashed {
             report.add_break();
-
-            let is_active_desc = if validator.is_active { "🟢" } else { "🔴" };
-            report.add_raw_text(format!(
-                "{} <b><a href=\"https://{}.subscan.io/validator/{}\">{}</a></b>",
-                is_active_desc,
-                data.network.name.to_lowercase(),
-                validator.stash,
-                validator.name,
-            ));
+            report.add_validator(&validator, &data.network);
+
+            let amount_value: u128 = data
+                .slashes
+                .iter()
+                .filter(|slash| slash.who.as_ref() == Some(&validator.stash))
+                .map(|slash| slash.amount_value)
+                .sum();
+            let slashed_amount = format!(
+                "{:.4} {}",
+                amount_value as f64 / 10f64.powi(data.network.token_decimals.into()),
+                data.network.token_symbol
+            );
 
             report.add_raw_text(format!(
                 "🤬 Slashed amount -> 💸 <b>{}</b>",
                 slashed_amount,
             ));
+            report.close_validator();
         }
     }
 
-    // Hook
-    report.add_break();
-    let exists_desc = if !data.slash.hook.filename_exists {
-        "❌"
-    } else {
-        ""
-    };
-    report.add_text(format!(
-        "🪝 <code>{}</code> {}",
-        data.slash.hook.filename, exists_desc
-    ));
-
-    let raw_output = String::from_utf8(data.slash.hook.stdout).unwrap();
-    // filter lines that start by special character '!'
-    for line in raw_output.lines().filter(|line| line.starts_with("!")) {
-        report.add_raw_text(format!("‣ {}", line.strip_prefix("!").unwrap()));
+    // Hooks
+    for slash in data.slashes {
+        for hook in slash.hooks {
+            report.add_break();
+            report.add_hook(hook);
+        }
     }
 
     report
@@ -427,31 +816,14 @@ fn section_chill(report: &mut Report, data: RawData) -> &Report {
     for validator in data.validators {
         if validator.is_chilled {
             report.add_break();
+            report.add_validator(&validator, &data.network);
 
-            let is_active_desc = if validator.is_active { "🟢" } else { "🔴" };
-            report.add_raw_text(format!(
-                "{} <b><a href=\"https://{}.subscan.io/validator/{}\">{}</a></b>",
-                is_active_desc,
-                data.network.name.to_lowercase(),
-                validator.stash,
-                validator.name,
-            ));
-
-            report.add_raw_text(format!("👆 Has been chilled -> 🥶"));
+            report.add_raw_text("👆 Has been chilled -> 🥶".to_string());
 
             for hook in validator.hooks {
-                let exists_desc = if !hook.filename_exists { "❌" } else { "" };
-                report.add_text(format!(
-                    "🪝 <code>{}</code> {}",
-                    hook.filename, exists_desc
-                ));
-
-                let raw_output = String::from_utf8(hook.stdout).unwrap();
-                // filter lines that start by special character '!'
-                for line in raw_output.lines().filter(|line| line.starts_with("!")) {
-                    report.add_raw_text(format!("‣ {}", line.strip_prefix("!").unwrap()));
-                }
+                report.add_hook(hook);
             }
+            report.close_validator();
         }
     }
 
@@ -470,51 +842,95 @@ fn section_offline(report: &mut Report, data: RawData) -> &Report {
     for validator in data.validators {
         if validator.is_offline {
             report.add_break();
+            report.add_validator(&validator, &data.network);
 
-            let is_active_desc = if validator.is_active { "🟢" } else { "🔴" };
             report.add_raw_text(format!(
-                "{} <b><a href=\"https://{}.subscan.io/validator/{}\">{}</a></b>",
-                is_active_desc,
-                data.network.name.to_lowercase(),
-                validator.stash,
-                validator.name,
+                "👆 Has been seen offline in the previous session -> ⛑️ <code>{}</code>",
+                validator.offline_signals.join(", ")
             ));
 
-            report.add_raw_text(format!("👆 Has been seen offline -> ⛑️"));
-
             for hook in validator.hooks {
-                let exists_desc = if !hook.filename_exists { "❌" } else { "" };
-                report.add_text(format!(
-                    "🪝 <code>{}</code> {}",
-                    hook.filename, exists_desc
-                ));
-
-                let raw_output = String::from_utf8(hook.stdout).unwrap();
-                // filter lines that start by special character '!'
-                for line in raw_output.lines().filter(|line| line.starts_with("!")) {
-                    report.add_raw_text(format!("‣ {}", line.strip_prefix("!").unwrap()));
-                }
+                report.add_hook(hook);
             }
+            report.close_validator();
+        }
+    }
+
+    report
+}
+
+fn section_runtime_upgrade(report: &mut Report, data: RawData) -> &Report {
+    // Network info
+    report.add_break();
+    report.add_raw_text(format!(
+        "⛓️ <b>{}</b> -> 🆙 Runtime upgraded from {} to <b>{}</b> at block <a href=\"https://{}.subscan.io/block/{}\">#{}</a>",
+        data.network.name,
+        data.runtime_upgrade.old_spec_version,
+        data.runtime_upgrade.new_spec_version,
+        data.network.name.to_lowercase(),
+        data.runtime_upgrade.block_number,
+        data.runtime_upgrade.block_number
+    ));
+
+    // Compatibility
+    report.add_break();
+    if data.runtime_upgrade.incompatibilities.is_empty() {
+        report.add_raw_text(
+            "✅ Storage entries and events used by scouty are compatible.".to_string(),
+        );
+    } else {
+        report.add_raw_text(
+            "⚠️ Storage entries and events used by scouty have changed:".to_string(),
+        );
+        for incompatibility in data.runtime_upgrade.incompatibilities {
+            report.add_raw_text(format!("‣ <code>{}</code>", incompatibility));
         }
     }
 
+    // Hooks
+    for hook in data.runtime_upgrade.hooks {
+        report.add_break();
+        report.add_hook(hook);
+    }
+
     report
 }
 
-fn session_flag(index: u32) -> String {
-    match index {
+/// Escape text that is not controlled by scouty (e.g. hook stderr) before being
+/// added to the formatted message
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Returns only the scheme, host and port of an endpoint, so that api keys
+/// eventually defined in the path or query are not shared
+fn endpoint_host(endpoint: &str) -> String {
+    match url::Url::parse(endpoint) {
+        Ok(url) => match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{}://{}:{}", url.scheme(), host, port),
+            (Some(host), None) => format!("{}://{}", url.scheme(), host),
+            _ => endpoint.to_string(),
+        },
+        Err(_) => endpoint.to_string(),
+    }
+}
+
+fn session_flag(session: &Session) -> String {
+    match session.eras_session_index {
+        _ if session.is_last_session_of_era() => "🏳️".to_string(),
         1 => "🎬".to_string(),
-        6 => "🏳️".to_string(),
         _ => "⏳".to_string(),
     }
 }
 
-fn session_ordinal_number(index: u32) -> String {
-    match index {
+fn session_ordinal_number(session: &Session) -> String {
+    match session.eras_session_index {
+        _ if session.is_last_session_of_era() => "<b>last</b>".to_string(),
         1 => "1st".to_string(),
         2 => "2nd".to_string(),
         3 => "3rd".to_string(),
-        6 => "<b>last</b>".to_string(),
-        _ => format!("{}th", index),
+        index => format!("{}th", index),
     }
 }
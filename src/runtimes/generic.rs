@@ -0,0 +1,2651 @@
+// The MIT License (MIT)
+// Copyright © 2021 Aukbit Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::authority::{AuthorityIndex, AuthorityRecords};
+use crate::config::{Config, CONFIG};
+use crate::errors::ScoutyError;
+use crate::hooks::{
+    Hook, HookArgs, HookCall, HOOK_CHAIN_EVENT, HOOK_INIT, HOOK_NEW_ERA,
+    HOOK_NEW_SESSION, HOOK_REFERENDA_SUBMITTED, HOOK_RUNTIME_UPGRADED, HOOK_SCHEDULED,
+    HOOK_VALIDATOR_CHILLED, HOOK_VALIDATOR_OFFLINE, HOOK_VALIDATOR_SLASHED,
+    HOOK_VALIDATOR_STARTS_ACTIVE_NEXT_ERA, HOOK_VALIDATOR_STARTS_INACTIVE_NEXT_ERA,
+};
+use crate::identity::Identity;
+use crate::incidents::Condition;
+use crate::para::ParaRecords;
+use crate::report::{
+    ChainEvent, Init, Network, Points, RawData, Referendum, RuntimeUpgrade, Section,
+    Session, Slash, Validator, Validators,
+};
+use crate::runtimes::types::{
+    fields_to_json, value_to_bytes, value_to_u32, ActiveEraInfo, Chilled, CodeUpdated,
+    DisabledValidator, Enter, EraRewardPoints, ExposurePage, IdentityData, NewSession,
+    Nominations, PagedExposureMetadata, PreDigest, SessionKeys, Slashed, StakingLedger,
+    Submitted, ValidatorPrefs,
+};
+use crate::scouty::{
+    convert_account_id, get_account_id_from_storage_key, Scouty, FINALIZED_BLOCK_TIMEOUT,
+};
+use crate::stats;
+use async_recursion::async_recursion;
+use async_std::future::timeout;
+use chrono::{DateTime, Utc};
+use codec::Decode;
+use log::{debug, info, warn};
+use std::{
+    collections::BTreeMap, convert::TryInto, result::Result, str::FromStr, time::Duration,
+};
+use subxt::{
+    backend::RuntimeVersion,
+    blocks::Block,
+    config::substrate::{Digest, DigestItem},
+    dynamic::{At, DecodedValue},
+    events::{Events, StaticEvent},
+    ext::{
+        scale_decode::DecodeAsType,
+        scale_value::{Value, ValueDef},
+        sp_core::{hexdisplay::HexDisplay, H256},
+    },
+    storage::address::{make_static_storage_map_key, Address, StaticStorageMapKey, Yes},
+    utils::AccountId32,
+    Metadata, OnlineClient, PolkadotConfig,
+};
+
+/// Storage entries read by scouty, used to verify runtime metadata compatibility
+const STORAGE_ENTRIES: [(&str, &str); 19] = [
+    ("System", "Number"),
+    ("Session", "CurrentIndex"),
+    ("Session", "Validators"),
+    ("Session", "QueuedKeys"),
+    ("Session", "QueuedChanged"),
+    ("Session", "DisabledValidators"),
+    ("Staking", "ActiveEra"),
+    ("Staking", "ErasStartSessionIndex"),
+    ("Staking", "ErasRewardPoints"),
+    ("Staking", "ErasValidatorReward"),
+    ("Staking", "ErasStakersPaged"),
+    ("Staking", "ErasStakersOverview"),
+    ("Staking", "Nominators"),
+    ("Staking", "Bonded"),
+    ("Staking", "Ledger"),
+    ("Staking", "Validators"),
+    ("Identity", "IdentityOf"),
+    ("Identity", "SuperOf"),
+    ("ParasShared", "ActiveValidatorIndices"),
+];
+
+/// Events subscribed by scouty, used to verify runtime metadata compatibility
+const EVENTS: [(&str, &str); 5] = [
+    (CodeUpdated::PALLET, CodeUpdated::EVENT),
+    (NewSession::PALLET, NewSession::EVENT),
+    (Slashed::PALLET, Slashed::EVENT),
+    (Chilled::PALLET, Chilled::EVENT),
+    (Submitted::PALLET, Submitted::EVENT),
+];
+
+type StorageAddress<ReturnTy> = Address<StaticStorageMapKey, ReturnTy, Yes, Yes, Yes>;
+
+/// Build a storage address for any pallet storage entry, decoded into `ReturnTy`
+fn storage<ReturnTy: DecodeAsType>(
+    pallet_name: &str,
+    entry_name: &str,
+    keys: Vec<StaticStorageMapKey>,
+) -> StorageAddress<ReturnTy> {
+    Address::new(pallet_name, entry_name, keys)
+}
+
+/// State carried across subscriptions, so that scouty resumes from the last
+/// processed block after reconnecting
+#[derive(Debug, Default)]
+pub struct Checkpoint {
+    last_block_number: Option<u32>,
+    authority_records: AuthorityRecords,
+    para_records: ParaRecords,
+    initial_session_index: u32,
+    // NOTE: next time the scheduled hook is due
+    scheduled_at: Option<DateTime<Utc>>,
+}
+
+/// Finalized blocks to be processed before a live block
+#[derive(Debug, PartialEq)]
+enum CatchUp {
+    /// The live block has already been processed before reconnecting
+    Processed,
+    /// No blocks were missed
+    None,
+    /// Blocks within the range were missed and are processed in order
+    Blocks(u32, u32),
+    /// Blocks within the range were missed but exceed the catch up limit
+    Exceeded(u32, u32),
+}
+
+impl Checkpoint {
+    /// Returns the finalized blocks missed before the live block, if any
+    fn catch_up(&self, block_number: u32, max_blocks: u32) -> CatchUp {
+        match self.last_block_number {
+            Some(last_block_number) if block_number <= last_block_number => {
+                CatchUp::Processed
+            }
+            Some(last_block_number) if block_number > last_block_number + 1 => {
+                let (from, to) = (last_block_number + 1, block_number - 1);
+                if to - from + 1 > max_blocks {
+                    CatchUp::Exceeded(from, to)
+                } else {
+                    CatchUp::Blocks(from, to)
+                }
+            }
+            _ => CatchUp::None,
+        }
+    }
+
+    /// Move the checkpoint forward to the block processed
+    fn advance(&mut self, block_number: u32) {
+        if self.last_block_number < Some(block_number) {
+            self.last_block_number = Some(block_number);
+        }
+    }
+}
+
+pub async fn init_and_subscribe_on_chain_events(
+    scouty: &Scouty,
+    checkpoint: &mut Checkpoint,
+) -> Result<(), ScoutyError> {
+    let config = CONFIG.clone();
+    let api = scouty.client().clone();
+
+    // Verify that the live runtime still matches the metadata scouty was built against
+    verify_metadata_compatibility(scouty);
+    verify_event_hooks(scouty);
+
+    // Initialize authority and para records on the first subscription only,
+    // records are kept across reconnects while missed blocks are caught up
+    match checkpoint.last_block_number {
+        Some(last_block_number) => {
+            info!("Resume after last processed block #{}", last_block_number)
+        }
+        None => init_checkpoint_records(scouty, checkpoint).await?,
+    }
+
+    // Start by calling init hook
+    try_init_hook(
+        scouty,
+        &checkpoint.authority_records,
+        &checkpoint.para_records,
+    )
+    .await?;
+    //
+    info!("Subscribe on-chain finalized blocks");
+    let mut blocks_sub = api.blocks().subscribe_finalized().await?;
+    // NOTE: finality not moving or the connection dropping are reported as an
+    // unhealthy endpoint, so that scouty switches to the next endpoint available
+    while let Some(block) = timeout(
+        Duration::from_secs(FINALIZED_BLOCK_TIMEOUT),
+        blocks_sub.next(),
+    )
+    .await
+    .map_err(|_| {
+        ScoutyError::EndpointUnhealthy(format!(
+            "no finalized blocks received in the last {} seconds",
+            FINALIZED_BLOCK_TIMEOUT
+        ))
+    })? {
+        let block = block.map_err(|e| ScoutyError::EndpointUnhealthy(e.to_string()))?;
+        let block_number = block.header().number;
+
+        match checkpoint.catch_up(block_number, config.catch_up_max_blocks) {
+            // Skip blocks already processed before reconnecting
+            CatchUp::Processed => continue,
+            // Process finalized blocks missed while scouty was disconnected
+            CatchUp::Blocks(from, to) => {
+                try_catch_up_blocks(scouty, checkpoint, from, to, block.hash()).await?
+            }
+            CatchUp::Exceeded(from, to) => {
+                // NOTE: state of older blocks is most likely pruned by the node,
+                // so records are initialized again from the live block
+                warn!(
+                    "{} finalized blocks missed (#{} to #{}) exceed the catch up limit of {} blocks, skipping them",
+                    to - from + 1, from, to, config.catch_up_max_blocks
+                );
+                scouty.set_block_hash(Some(block.hash()));
+                init_checkpoint_records(scouty, checkpoint).await?;
+            }
+            CatchUp::None => (),
+        }
+
+        process_block(scouty, &block, checkpoint).await?;
+
+        // Scheduled hook, independent of chain events
+        try_run_scheduled_hook(scouty, checkpoint, block_number).await?;
+    }
+    // If subscription has closed for some reason await and subscribe again
+    Err(ScoutyError::SubscriptionFinished)
+}
+
+/// Initialize authority and para records at the block currently set in scouty
+async fn init_checkpoint_records(
+    scouty: &Scouty,
+    checkpoint: &mut Checkpoint,
+) -> Result<(), ScoutyError> {
+    let mut authority_records = AuthorityRecords::new();
+    init_authority_records(scouty, &mut authority_records).await?;
+
+    let mut para_records = ParaRecords::new();
+    init_para_records(scouty, &mut para_records).await?;
+
+    // Offline signals are only evaluated for sessions fully tracked by scouty
+    checkpoint.initial_session_index = authority_records.current_session_index();
+    checkpoint.authority_records = authority_records;
+    checkpoint.para_records = para_records;
+    Ok(())
+}
+
+/// Process in order the finalized blocks within the given range, missed while
+/// scouty was disconnected, before carrying on with the live block
+async fn try_catch_up_blocks(
+    scouty: &Scouty,
+    checkpoint: &mut Checkpoint,
+    from: u32,
+    to: u32,
+    live_block_hash: H256,
+) -> Result<(), ScoutyError> {
+    let api = scouty.client().clone();
+
+    info!("Catch up on finalized blocks from #{} to #{}", from, to);
+
+    // Decode storage and events with the runtime enacted at the first missed block
+    let from_hash = get_block_hash(scouty, from).await?;
+    let live_spec_version = api.runtime_version().spec_version;
+    update_runtime(scouty, from_hash).await?;
+
+    for block_number in from..=to {
+        let block_hash = get_block_hash(scouty, block_number).await?;
+        let block = api.blocks().at(block_hash).await?;
+        process_block(scouty, &block, checkpoint).await?;
+    }
+
+    // Make sure the live block is decoded with the live runtime
+    if api.runtime_version().spec_version != live_spec_version {
+        update_runtime(scouty, live_block_hash).await?;
+    }
+
+    info!("Caught up on {} finalized blocks", to - from + 1);
+    Ok(())
+}
+
+/// Walk finalized blocks from an archive node within the given range and push
+/// them through the same event handlers and hooks as in live mode
+pub async fn init_and_replay_on_chain_events(
+    scouty: &Scouty,
+    from: u32,
+    to: u32,
+) -> Result<(), ScoutyError> {
+    let api = scouty.client().clone();
+
+    let from_hash = get_block_hash(scouty, from).await?;
+
+    // Decode storage and events with the runtime enacted at the first block
+    update_runtime(scouty, from_hash).await?;
+    info!(
+        "Replay blocks from #{} to #{} starting on runtime {}",
+        from,
+        to,
+        api.runtime_version().spec_version
+    );
+
+    // Initialize authority and para records at the first block
+    scouty.set_block_hash(Some(from_hash));
+    let mut checkpoint = Checkpoint::default();
+    init_checkpoint_records(scouty, &mut checkpoint).await?;
+
+    for block_number in from..=to {
+        let block_hash = get_block_hash(scouty, block_number).await?;
+        let block = api.blocks().at(block_hash).await?;
+        process_block(scouty, &block, &mut checkpoint).await?;
+    }
+    scouty.set_block_hash(None);
+
+    info!("Replay finished at block #{}", to);
+    Ok(())
+}
+
+/// Run a single hook for the stashes defined against the latest or a historical finalized block,
+/// through the same event handlers, so that hook arguments are exactly the ones produced on chain events
+pub async fn init_and_test_hook(
+    scouty: &Scouty,
+    hook: &str,
+    at: Option<u32>,
+) -> Result<(), ScoutyError> {
+    let config = CONFIG.clone();
+
+    let block_number = match at {
+        Some(block_number) => block_number,
+        None => {
+            let finalized_hash = scouty.rpc().chain_get_finalized_head().await?;
+            match scouty.rpc().chain_get_header(Some(finalized_hash)).await? {
+                Some(header) => header.number,
+                None => return Err("Finalized block header not found".into()),
+            }
+        }
+    };
+
+    // Decode storage and events with the runtime enacted at the block
+    let block_hash = get_block_hash(scouty, block_number).await?;
+    update_runtime(scouty, block_hash).await?;
+    scouty.set_block_hash(Some(block_hash));
+    info!("Test hook {} at block #{}", hook, block_number);
+
+    let mut checkpoint = Checkpoint::default();
+    init_checkpoint_records(scouty, &mut checkpoint).await?;
+
+    let mut stashes: Vec<AccountId32> = Vec::new();
+    for stash_str in config.stashes.iter() {
+        let stash = AccountId32::from_str(stash_str).map_err(|e| {
+            ScoutyError::Other(format!(
+                "Invalid SS58 format account: {:?} error: {e:?}",
+                stash_str
+            ))
+        })?;
+        stashes.push(stash);
+    }
+
+    // NOTE: events are made up for the stashes defined, and session hooks run
+    // regardless of the session of the era
+    let data = match hook {
+        HOOK_INIT => {
+            try_init_hook(
+                scouty,
+                &checkpoint.authority_records,
+                &checkpoint.para_records,
+            )
+            .await?;
+            None
+        }
+        HOOK_NEW_SESSION
+        | HOOK_NEW_ERA
+        | HOOK_VALIDATOR_STARTS_ACTIVE_NEXT_ERA
+        | HOOK_VALIDATOR_STARTS_INACTIVE_NEXT_ERA => {
+            let session_index = checkpoint.authority_records.current_session_index();
+            let data = SessionHookData::load(scouty, session_index).await?;
+            let data = run_session_hooks(
+                scouty,
+                data,
+                block_number,
+                &checkpoint.authority_records,
+                &checkpoint.para_records,
+                |_, _| vec![hook],
+            )
+            .await?;
+            Some(data)
+        }
+        HOOK_SCHEDULED => {
+            run_scheduled_hook(
+                scouty,
+                block_number,
+                &checkpoint.authority_records,
+                &checkpoint.para_records,
+            )
+            .await?;
+            None
+        }
+        HOOK_VALIDATOR_CHILLED => {
+            let events = stashes.into_iter().map(|stash| Chilled { stash }).collect();
+            try_run_staking_chilled_hook(scouty, events).await?
+        }
+        HOOK_VALIDATOR_SLASHED => {
+            let events = stashes
+                .into_iter()
+                .map(|staker| Slashed { staker, amount: 0 })
+                .collect();
+            try_run_staking_slashed_hook(scouty, events).await?
+        }
+        _ => {
+            return Err(ScoutyError::Other(format!(
+                "{} hook can not be tested against chain data",
+                hook
+            )))
+        }
+    };
+    if let Some(data) = data {
+        scouty.notify(data).await?;
+    }
+    scouty.set_block_hash(None);
+
+    Ok(())
+}
+
+/// Process a finalized block and move the checkpoint forward once every hook has run,
+/// notifications are only sent afterwards so that a notifier failing never causes
+/// the block to be processed again
+async fn process_block(
+    scouty: &Scouty,
+    block: &Block<PolkadotConfig, OnlineClient<PolkadotConfig>>,
+    checkpoint: &mut Checkpoint,
+) -> Result<(), ScoutyError> {
+    let block_number = block.header().number;
+    let notifications = run_block_hooks(
+        scouty,
+        block,
+        &mut checkpoint.authority_records,
+        &mut checkpoint.para_records,
+        checkpoint.initial_session_index,
+    )
+    .await?;
+    checkpoint.advance(block_number);
+
+    for data in notifications.into_iter() {
+        if let Err(e) = scouty.notify(data).await {
+            warn!("Block #{} notification not sent: {}", block_number, e);
+        }
+    }
+    Ok(())
+}
+
+/// Run the event handlers and hooks for a finalized block, reading storage at that block,
+/// and returns the notifications to be sent
+async fn run_block_hooks(
+    scouty: &Scouty,
+    block: &Block<PolkadotConfig, OnlineClient<PolkadotConfig>>,
+    authority_records: &mut AuthorityRecords,
+    para_records: &mut ParaRecords,
+    initial_session_index: u32,
+) -> Result<Vec<RawData>, ScoutyError> {
+    let block_number = block.header().number;
+    let events = block.events().await?;
+    let block_hash = events.block_hash();
+    scouty.set_block_hash(Some(block_hash));
+
+    // Block authors are only tracked on chains running BABE
+    let authority_index = if scouty.features().babe {
+        get_authority_index(scouty, Some(block_hash)).await?
+    } else {
+        None
+    };
+
+    let mut notifications: Vec<RawData> = Vec::new();
+
+    // Event --> session::NewSession
+    let event = events.find_first::<NewSession>()?;
+    notifications.extend(
+        try_run_session_hooks(
+            scouty,
+            event.as_ref(),
+            authority_records,
+            block_number,
+            authority_index,
+            para_records,
+        )
+        .await?,
+    );
+
+    // Offline signals from the previous session, evaluated on a new session
+    notifications.extend(
+        try_run_validator_offline_hooks(
+            scouty,
+            event.as_ref(),
+            block.header().parent_hash,
+            authority_records,
+            para_records,
+            initial_session_index,
+        )
+        .await?,
+    );
+
+    // Track availability bitfields signed by para validators
+    if scouty.features().paras_shared {
+        let extrinsics = block.extrinsics().await?;
+        // NOTE: a decoding failure only affects offline signals, so monitoring carries on
+        match extrinsics.find_first::<Enter>() {
+            Ok(Some(ext)) => {
+                let bitfield_indices = ext
+                    .value
+                    .data
+                    .bitfields
+                    .iter()
+                    .map(|bitfield| bitfield.validator_index)
+                    .collect();
+                para_records.insert_bitfields(bitfield_indices);
+            }
+            Ok(None) => (),
+            Err(e) => warn!("Para inherent could not be decoded: {}", e),
+        }
+    }
+
+    // Event --> staking::Slashed
+    let events_slashed = events.find::<Slashed>().collect::<Result<Vec<_>, _>>()?;
+    notifications.extend(try_run_staking_slashed_hook(scouty, events_slashed).await?);
+
+    // Event --> staking::Chilled
+    let events_chilled = events.find::<Chilled>().collect::<Result<Vec<_>, _>>()?;
+    notifications.extend(try_run_staking_chilled_hook(scouty, events_chilled).await?);
+
+    // Event --> referenda::Submitted
+    if scouty.features().referenda {
+        let events_submitted =
+            events.find::<Submitted>().collect::<Result<Vec<_>, _>>()?;
+        notifications
+            .extend(try_run_referenda_submitted_hook(scouty, events_submitted).await?);
+    }
+
+    // Any event --> event hooks
+    notifications.extend(try_run_event_hooks(scouty, &events, block_number).await?);
+
+    // Track authority record
+    authority_records.insert_record(block_number, authority_index)?;
+
+    // Event --> system::CodeUpdated
+    // Note: handled last so that all events in this block are decoded
+    // with the metadata of the runtime that emitted them
+    let event = events.find_first::<CodeUpdated>()?;
+    notifications.extend(
+        try_run_runtime_upgraded_hook(scouty, event, block_number, block_hash).await?,
+    );
+
+    Ok(notifications)
+}
+
+async fn get_block_hash(scouty: &Scouty, block_number: u32) -> Result<H256, ScoutyError> {
+    match scouty
+        .rpc()
+        .chain_get_block_hash(Some(block_number.into()))
+        .await?
+    {
+        Some(block_hash) => Ok(block_hash),
+        None => Err(ScoutyError::Other(format!(
+            "Block #{} not found",
+            block_number
+        ))),
+    }
+}
+
+/// Update client metadata and runtime version to the runtime enacted at the given block
+async fn update_runtime(scouty: &Scouty, block_hash: H256) -> Result<(), ScoutyError> {
+    let version = scouty
+        .rpc()
+        .state_get_runtime_version(Some(block_hash))
+        .await?;
+    let metadata = scouty.rpc().state_get_metadata(Some(block_hash)).await?;
+    scouty.client().set_metadata(metadata);
+    scouty.client().set_runtime_version(RuntimeVersion {
+        spec_version: version.spec_version,
+        transaction_version: version.transaction_version,
+    });
+    Ok(())
+}
+
+/// Compare the storage entries and events used by scouty between the metadata
+/// scouty was built against and the metadata of the connected runtime
+fn verify_metadata_compatibility(scouty: &Scouty) {
+    if let Some(bytes) = scouty.runtime().metadata() {
+        let expected = match Metadata::decode(&mut &bytes[..]) {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                warn!("{} metadata could not be decoded: {}", scouty.runtime(), e);
+                return;
+            }
+        };
+        let live = scouty.client().metadata();
+
+        for incompatibility in metadata_incompatibilities(&expected, &live) {
+            warn!(
+                "{} compared to {} metadata scouty was built against",
+                incompatibility,
+                scouty.runtime()
+            );
+        }
+    }
+}
+
+/// Warn about event hooks on events that are not available in the connected runtime
+fn verify_event_hooks(scouty: &Scouty) {
+    let metadata = scouty.client().metadata();
+    for event_hook in CONFIG.event_hooks.iter() {
+        let is_available = metadata.pallets().any(|pallet| {
+            pallet.event_variants().is_some_and(|variants| {
+                variants.iter().any(|variant| {
+                    event_hook.event.is_event(pallet.name(), &variant.name)
+                })
+            })
+        });
+        if !is_available {
+            warn!(
+                "Event {} is not available, hook {} will never run",
+                event_hook.event, event_hook.path
+            );
+        }
+    }
+}
+
+/// Returns the storage entries and events used by scouty that are missing or
+/// have changed in `live` metadata compared to `expected` metadata
+fn metadata_incompatibilities(expected: &Metadata, live: &Metadata) -> Vec<String> {
+    let mut incompatibilities = Vec::new();
+
+    for (pallet_name, entry_name) in STORAGE_ENTRIES {
+        let storage_hash = |metadata: &Metadata| {
+            metadata
+                .pallet_by_name(pallet_name)
+                .and_then(|pallet| pallet.storage_hash(entry_name))
+        };
+        match (storage_hash(expected), storage_hash(live)) {
+            (Some(_), None) => incompatibilities.push(format!(
+                "Storage {}::{} is not available",
+                pallet_name, entry_name
+            )),
+            (Some(a), Some(b)) if a != b => incompatibilities.push(format!(
+                "Storage {}::{} has changed",
+                pallet_name, entry_name
+            )),
+            _ => (),
+        }
+    }
+
+    for (pallet_name, event_name) in EVENTS {
+        match (
+            event_hash(expected, pallet_name, event_name),
+            event_hash(live, pallet_name, event_name),
+        ) {
+            (Some(_), None) => incompatibilities.push(format!(
+                "Event {}::{} is not available",
+                pallet_name, event_name
+            )),
+            (Some(a), Some(b)) if a != b => incompatibilities
+                .push(format!("Event {}::{} has changed", pallet_name, event_name)),
+            _ => (),
+        }
+    }
+
+    incompatibilities
+}
+
+/// Returns the field names and type hashes of a pallet event
+fn event_hash(
+    metadata: &Metadata,
+    pallet_name: &str,
+    event_name: &str,
+) -> Option<Vec<(Option<String>, [u8; 32])>> {
+    let pallet = metadata.pallet_by_name(pallet_name)?;
+    let variant = pallet
+        .event_variants()?
+        .iter()
+        .find(|variant| variant.name == event_name)?;
+    variant
+        .fields
+        .iter()
+        .map(|field| Some((field.name.clone(), metadata.type_hash(field.ty.id)?)))
+        .collect()
+}
+
+/// Returns the hook arguments common to every validator stash
+fn validator_args(v: &Validator) -> HookArgs {
+    let mut args = HookArgs::new();
+    args.push("stash", convert_account_id(v.stash.clone()).to_string());
+    args.push("identity", v.name.to_string());
+    args.push(
+        "queued_session_keys",
+        format!("0x{:?}", HexDisplay::from(&v.queued_session_keys)),
+    );
+    args.push("is_active", v.is_active);
+    args.push("is_queued", v.is_queued);
+    args
+}
+
+/// Push network hook arguments if exposed, otherwise fill them with "-"
+fn push_network_args(args: &mut HookArgs, network: &Network, config: &Config) {
+    if config.expose_network || config.expose_all {
+        args.push("network.name", network.name.to_string());
+        args.push("network.token_symbol", network.token_symbol.to_string());
+        args.push("network.token_decimals", network.token_decimals);
+    } else {
+        args.skip(3);
+    }
+}
+
+async fn try_init_hook(
+    scouty: &Scouty,
+    authority_records: &AuthorityRecords,
+    para_records: &ParaRecords,
+) -> Result<(), ScoutyError> {
+    let config = CONFIG.clone();
+
+    // Get the current block number being processed
+    let block_number_addr = storage::<u32>("System", "Number", vec![]);
+    let block_number = scouty
+        .storage()
+        .await?
+        .fetch(&block_number_addr)
+        .await?
+        .ok_or("Block number not defined")?;
+
+    let init = Init { block_number };
+
+    // Collect session data
+    let current_session_index_addr = storage::<u32>("Session", "CurrentIndex", vec![]);
+    let current_session_index = match scouty
+        .storage()
+        .await?
+        .fetch(&current_session_index_addr)
+        .await?
+    {
+        Some(index) => index,
+        None => return Err("Current session index not defined".into()),
+    };
+
+    let session = collect_session_data(scouty, current_session_index).await?;
+
+    let network = Network::load(scouty.rpc(), scouty.endpoint()).await?;
+    debug!("network {:?}", network);
+
+    // Sync all nominators
+    let all_nominators_map =
+        if config.any_stash(|c| c.expose_all_nominators || c.expose_all) {
+            get_nominators(scouty).await?
+        } else {
+            BTreeMap::new()
+        };
+
+    // Fetch era reward points and reward from previous era
+    let (era_reward_points, era_reward) =
+        fetch_previous_era_rewards(scouty, session.active_era_index).await?;
+
+    // Collect session active validators
+    let validators_addr = storage::<Vec<AccountId32>>("Session", "Validators", vec![]);
+    let active_validators = scouty
+        .storage()
+        .await?
+        .fetch(&validators_addr)
+        .await?
+        .unwrap_or_default();
+
+    // Collect validators info based on config stashes
+    let mut validators = collect_validators_data(scouty).await?;
+
+    // Try to run hooks for each stash
+    let mut hook_queues: Vec<Vec<HookCall>> = vec![Vec::new(); validators.len()];
+    for (i, v) in validators.iter_mut().enumerate() {
+        let config = config.for_stash(&v.stash);
+
+        // Try HOOK_INIT
+        let mut args = validator_args(v);
+        args.push("session.active_era_index", session.active_era_index);
+        args.push(
+            "session.current_session_index",
+            session.current_session_index,
+        );
+        args.push("session.eras_session_index", session.eras_session_index);
+        args.push("block_number", block_number);
+
+        push_network_args(&mut args, &network, &config);
+
+        if v.is_active && (config.expose_nominators || config.expose_all) {
+            // get active nominators info
+            let (
+                total_active_stake,
+                own_stake,
+                active_nominators,
+                active_nominators_stake,
+            ) = get_active_nominators(scouty, session.active_era_index, &v.stash).await?;
+
+            // calculate APR
+            let apr = calculate_projected_apr(
+                scouty,
+                &v.stash,
+                network.token_decimals,
+                total_active_stake,
+                era_reward,
+                active_validators.len().try_into().unwrap(),
+            )
+            .await?;
+            //
+            args.push("nominators.apr", format!("{:.2}", apr * 100.0));
+            args.push("nominators.total_stake", total_active_stake.to_string());
+            args.push("nominators.own_stake", own_stake.to_string());
+            args.push("nominators.active_stashes", active_nominators);
+            args.push(
+                "nominators.active_stakes",
+                active_nominators_stake
+                    .iter()
+                    .map(|x| x.to_string())
+                    .collect::<Vec<String>>(),
+            );
+        } else {
+            args.skip(5);
+        }
+
+        if v.is_active
+            && scouty.features().babe
+            && (config.expose_authored_blocks || config.expose_all)
+        {
+            let current_session_total = authority_records.current_session_total(&v.stash);
+            args.push("authored_blocks.current_session", current_session_total);
+            args.skip(1);
+        } else {
+            args.skip(2);
+        }
+
+        if config.expose_all_nominators || config.expose_all {
+            if let Some(all_nominators) = all_nominators_map.get(&v.stash.to_string()) {
+                let all_nominators_stashes = all_nominators
+                    .iter()
+                    .map(|(x, _, _)| x.to_string())
+                    .collect::<Vec<String>>();
+
+                let total_nominators_stake: u128 =
+                    all_nominators.iter().map(|(_, x, _)| x).sum();
+
+                let raw_nominees_stake: u128 =
+                    all_nominators.iter().map(|(_, x, y)| x / *y as u128).sum();
+                args.push("all_nominators.stashes", all_nominators_stashes);
+                args.push(
+                    "all_nominators.stake",
+                    vec![
+                        total_nominators_stake.to_string(),
+                        raw_nominees_stake.to_string(),
+                    ],
+                );
+            } else {
+                args.skip(2);
+            }
+        } else {
+            args.skip(2);
+        }
+
+        if v.is_active
+            && scouty.features().paras_shared
+            && (config.expose_para_validator || config.expose_all)
+        {
+            let is_para_validator = para_records.is_para_validator(&v.stash);
+            args.push("para.is_para_validator", is_para_validator);
+            args.skip(1);
+        } else {
+            args.skip(2);
+        }
+
+        if v.is_active && (config.expose_era_points || config.expose_all) {
+            let points = get_validator_points_info(&v.stash, era_reward_points.clone());
+            args.push("era_points.validator", points.validator);
+            args.push("era_points.era_avg", points.era_avg);
+        } else {
+            args.skip(2);
+        }
+
+        hook_queues[i].push(HookCall::new(HOOK_INIT, &config.hook_init_path, args));
+    }
+
+    // Try run hooks, in order for each stash and concurrently across stashes
+    let hooks = Hook::run_queues(hook_queues).await;
+    for (v, hooks) in validators.iter_mut().zip(hooks) {
+        v.hooks.extend(hooks);
+    }
+
+    // Prepare notification report
+    debug!("validators {:?}", validators);
+
+    let data = RawData {
+        init,
+        network,
+        session,
+        validators,
+        section: Section::Init,
+        ..Default::default()
+    };
+
+    scouty.notify(data).await?;
+
+    Ok(())
+}
+
+async fn try_run_staking_chilled_hook(
+    scouty: &Scouty,
+    events: Vec<Chilled>,
+) -> Result<Option<RawData>, ScoutyError> {
+    if !events.is_empty() {
+        let config = CONFIG.clone();
+
+        // Collect validators info based on config stashes
+        let mut validators = collect_validators_data(scouty).await?;
+
+        let network = Network::load(scouty.rpc(), scouty.endpoint()).await?;
+        debug!("network {:?}", network);
+
+        // Try to run hooks for each stash
+        let mut hook_queues: Vec<Vec<HookCall>> = vec![Vec::new(); validators.len()];
+        for (i, v) in validators.iter_mut().enumerate() {
+            // Identify if the stash has been chilled
+            if events.iter().any(|event| event.stash == v.stash) {
+                v.is_chilled = true;
+                let config = config.for_stash(&v.stash);
+
+                // Try HOOK_VALIDATOR_CHILLED
+                let mut args = validator_args(v);
+
+                push_network_args(&mut args, &network, &config);
+
+                hook_queues[i].push(HookCall::new(
+                    HOOK_VALIDATOR_CHILLED,
+                    &config.hook_validator_chilled_path,
+                    args,
+                ));
+            }
+        }
+
+        // Try run hooks, in order for each stash and concurrently across stashes
+        let hooks = Hook::run_queues(hook_queues).await;
+        for (v, hooks) in validators.iter_mut().zip(hooks) {
+            v.hooks.extend(hooks);
+        }
+
+        debug!("validators {:?}", validators);
+
+        // NOTE: Only send chilled message if the chilled account is
+        // one of the stashes defined in config
+        if validators.iter().any(|v| v.is_chilled) {
+            // Prepare notification report
+            let session = collect_session_data(scouty, 0).await?;
+            let data = RawData {
+                network,
+                session,
+                validators,
+                section: Section::Chill,
+                ..Default::default()
+            };
+
+            return Ok(Some(data));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Returns the signals from which a validator stash is seen offline in the previous session
+fn offline_signals(
+    is_disabled: bool,
+    authored_blocks: Option<u32>,
+    was_para_validator: Option<bool>,
+    bitfields: u32,
+    has_paras: bool,
+) -> Vec<String> {
+    let mut signals: Vec<String> = Vec::new();
+    if is_disabled {
+        signals.push("disabled".to_string());
+    }
+
+    // NOTE: authored blocks are only tracked on chains running BABE
+    let no_authored_blocks = authored_blocks == Some(0);
+
+    if was_para_validator == Some(true) {
+        // Para validators sign availability bitfields in almost every block
+        if bitfields == 0 && (no_authored_blocks || authored_blocks.is_none()) {
+            signals.push("no_bitfields".to_string());
+            if no_authored_blocks {
+                signals.push("no_authored_blocks".to_string());
+            }
+        }
+    } else if !has_paras && no_authored_blocks {
+        signals.push("no_authored_blocks".to_string());
+    }
+    signals
+}
+
+async fn try_run_validator_offline_hooks(
+    scouty: &Scouty,
+    event: Option<&NewSession>,
+    parent_hash: H256,
+    authority_records: &AuthorityRecords,
+    para_records: &ParaRecords,
+    initial_session_index: u32,
+) -> Result<Option<RawData>, ScoutyError> {
+    if let Some(event) = event {
+        // Skip sessions that were only partially tracked
+        if event.session_index <= initial_session_index + 1 {
+            return Ok(None);
+        }
+        let api = scouty.client().clone();
+        let config = CONFIG.clone();
+
+        // Fetch validators and disabled validators from the last block of the previous session
+        let validators_addr =
+            storage::<Vec<AccountId32>>("Session", "Validators", vec![]);
+        let previous_validators = api
+            .storage()
+            .at(parent_hash)
+            .fetch(&validators_addr)
+            .await?
+            .unwrap_or_default();
+
+        let disabled_validators_addr =
+            storage::<Vec<DisabledValidator>>("Session", "DisabledValidators", vec![]);
+        let disabled_validators: Vec<u32> = api
+            .storage()
+            .at(parent_hash)
+            .fetch(&disabled_validators_addr)
+            .await?
+            .unwrap_or_default()
+            .iter()
+            .filter_map(value_to_u32)
+            .collect();
+
+        // Evaluate offline signals of the config stashes active in the previous session
+        let mut offline_stashes: Vec<(AccountId32, Vec<String>)> = Vec::new();
+        let mut online_stashes: Vec<AccountId32> = Vec::new();
+        for stash in config
+            .stashes
+            .iter()
+            .filter_map(|stash| AccountId32::from_str(stash).ok())
+        {
+            // Only validators active in the previous session can be seen offline
+            let index = match previous_validators.iter().position(|x| x == &stash) {
+                Some(index) => index as u32,
+                None => continue,
+            };
+            let signals = offline_signals(
+                disabled_validators.contains(&index),
+                scouty
+                    .features()
+                    .babe
+                    .then(|| authority_records.previous_session_total(&stash)),
+                para_records.was_para_validator(&stash),
+                para_records.previous_session_bitfields(&stash),
+                scouty.features().paras_shared,
+            );
+            if signals.is_empty() {
+                online_stashes.push(stash);
+            } else {
+                offline_stashes.push((stash, signals));
+            }
+        }
+
+        // NOTE: Only collect data and send the offline message if one of
+        // the stashes defined in config has been seen offline, stashes seen
+        // online are still passed on so that their offline incidents are resolved
+        if offline_stashes.is_empty() {
+            scouty
+                .clear_incidents(Condition::Offline, &online_stashes)
+                .await;
+            return Ok(None);
+        }
+
+        // Collect validators info based on config stashes
+        let mut validators = collect_validators_data(scouty).await?;
+
+        let network = Network::load(scouty.rpc(), scouty.endpoint()).await?;
+        debug!("network {:?}", network);
+
+        // Try to run hooks for each stash
+        let mut hook_queues: Vec<Vec<HookCall>> = vec![Vec::new(); validators.len()];
+        for (i, v) in validators.iter_mut().enumerate() {
+            let signals =
+                match offline_stashes.iter().find(|(stash, _)| stash == &v.stash) {
+                    Some((_, signals)) => signals,
+                    None => continue,
+                };
+
+            v.is_offline = true;
+            v.offline_signals = signals.clone();
+            let config = config.for_stash(&v.stash);
+
+            // Try HOOK_VALIDATOR_OFFLINE
+            let mut args = validator_args(v);
+
+            push_network_args(&mut args, &network, &config);
+
+            args.push("offline_signals", v.offline_signals.clone());
+
+            hook_queues[i].push(HookCall::new(
+                HOOK_VALIDATOR_OFFLINE,
+                &config.hook_validator_offline_path,
+                args,
+            ));
+        }
+
+        // Try run hooks, in order for each stash and concurrently across stashes
+        let hooks = Hook::run_queues(hook_queues).await;
+        for (v, hooks) in validators.iter_mut().zip(hooks) {
+            v.hooks.extend(hooks);
+        }
+
+        debug!("validators {:?}", validators);
+
+        // Prepare notification report
+        let session = collect_session_data(scouty, 0).await?;
+        let data = RawData {
+            network,
+            session,
+            validators,
+            section: Section::Offline,
+            ..Default::default()
+        };
+
+        return Ok(Some(data));
+    }
+
+    Ok(None)
+}
+
+async fn try_run_staking_slashed_hook(
+    scouty: &Scouty,
+    events: Vec<Slashed>,
+) -> Result<Option<RawData>, ScoutyError> {
+    if !events.is_empty() {
+        let config = CONFIG.clone();
+
+        // Collect validators info based on config stashes
+        let mut validators = collect_validators_data(scouty).await?;
+
+        let network = Network::load(scouty.rpc(), scouty.endpoint()).await?;
+        debug!("network {:?}", network);
+
+        let (mut slashes, hook_queues) =
+            prepare_slashes(events, &mut validators, &network, &config);
+
+        debug!("validators {:?}", validators);
+
+        // Try run hooks
+        let hooks = Hook::run_queues(hook_queues).await;
+        for (slash, hooks) in slashes.iter_mut().zip(hooks) {
+            slash.hooks = hooks;
+        }
+
+        // Prepare notification report with all slashes in the block
+        let session = collect_session_data(scouty, 0).await?;
+        let data = RawData {
+            network,
+            session,
+            validators,
+            slashes,
+            section: Section::Slash,
+            ..Default::default()
+        };
+
+        return Ok(Some(data));
+    }
+    Ok(None)
+}
+
+/// Identify which stashes have been slashed and returns every slash in the block,
+/// together with the hook calls to be run for each one of them
+fn prepare_slashes(
+    events: Vec<Slashed>,
+    validators: &mut Validators,
+    network: &Network,
+    config: &Config,
+) -> (Vec<Slash>, Vec<Vec<HookCall>>) {
+    for v in validators.iter_mut() {
+        if events.iter().any(|event| event.staker == v.stash) {
+            v.is_slashed = true;
+        }
+    }
+
+    let mut slashes: Vec<Slash> = Vec::new();
+    let mut hook_queues: Vec<Vec<HookCall>> = Vec::new();
+    for event in events.into_iter() {
+        let config = config.for_stash(&event.staker);
+        let mut args = HookArgs::new();
+        args.push("stash", event.staker.to_string());
+        args.push("amount", event.amount.to_string());
+
+        push_network_args(&mut args, network, &config);
+
+        hook_queues.push(vec![HookCall::new(
+            HOOK_VALIDATOR_SLASHED,
+            &config.hook_validator_slashed_path,
+            args,
+        )]);
+
+        // Set slash info
+        slashes.push(Slash {
+            who: Some(event.staker),
+            amount_value: event.amount,
+            ..Default::default()
+        });
+    }
+    (slashes, hook_queues)
+}
+
+async fn try_run_referenda_submitted_hook(
+    scouty: &Scouty,
+    events: Vec<Submitted>,
+) -> Result<Option<RawData>, ScoutyError> {
+    if !events.is_empty() {
+        let config = CONFIG.clone();
+
+        let network = Network::load(scouty.rpc(), scouty.endpoint()).await?;
+        debug!("network {:?}", network);
+
+        // Try to run hook for each referendum
+        let mut referenda: Vec<Referendum> = Vec::new();
+        let mut hook_queues: Vec<Vec<HookCall>> = Vec::new();
+        for event in events.into_iter() {
+            let mut args = HookArgs::new();
+            args.push("referendum_index", event.index);
+            args.push("track", event.track);
+
+            push_network_args(&mut args, &network, &config);
+
+            hook_queues.push(vec![HookCall::new(
+                HOOK_REFERENDA_SUBMITTED,
+                &config.hook_referenda_submitted_path,
+                args,
+            )]);
+
+            // Set referendum info
+            referenda.push(Referendum {
+                index: event.index,
+                track: event.track,
+                ..Default::default()
+            });
+        }
+
+        // Try run hooks
+        let hooks = Hook::run_queues(hook_queues).await;
+        for (referendum, hooks) in referenda.iter_mut().zip(hooks) {
+            referendum.hooks = hooks;
+        }
+
+        // Prepare notification report with all referenda submitted in the block
+        let data = RawData {
+            network,
+            referenda,
+            section: Section::Referenda,
+            ..Default::default()
+        };
+
+        return Ok(Some(data));
+    }
+    Ok(None)
+}
+
+async fn try_run_event_hooks(
+    scouty: &Scouty,
+    events: &Events<PolkadotConfig>,
+    block_number: u32,
+) -> Result<Option<RawData>, ScoutyError> {
+    let config = CONFIG.clone();
+    if config.event_hooks.is_empty() {
+        return Ok(None);
+    }
+
+    let stashes: Vec<AccountId32> = config
+        .stashes
+        .iter()
+        .filter_map(|stash| AccountId32::from_str(stash).ok())
+        .collect();
+    let metadata = scouty.client().metadata();
+
+    // Decode the fields of events with hooks and keep the hook paths of every filter matched
+    let mut chain_events: Vec<(ChainEvent, Vec<String>)> = Vec::new();
+    for event in events.iter() {
+        let event = event?;
+        let event_hooks: Vec<_> = config
+            .event_hooks
+            .iter()
+            .filter(|h| h.event.is_event(event.pallet_name(), event.variant_name()))
+            .collect();
+        if event_hooks.is_empty() {
+            continue;
+        }
+
+        // NOTE: a decoding failure only affects event hooks, so monitoring carries on
+        let fields = match event.field_values() {
+            Ok(fields) => fields_to_json(&fields, metadata.types()),
+            Err(e) => {
+                warn!(
+                    "Event {}.{} could not be decoded: {}",
+                    event.pallet_name(),
+                    event.variant_name(),
+                    e
+                );
+                continue;
+            }
+        };
+        let paths: Vec<String> = event_hooks
+            .iter()
+            .filter(|h| h.event.matches(&fields, &stashes))
+            .map(|h| h.path.to_string())
+            .collect();
+        if !paths.is_empty() {
+            let chain_event = ChainEvent {
+                name: format!("{}.{}", event.pallet_name(), event.variant_name()),
+                block_number,
+                fields,
+                ..Default::default()
+            };
+            chain_events.push((chain_event, paths));
+        }
+    }
+
+    if chain_events.is_empty() {
+        return Ok(None);
+    }
+
+    let network = Network::load(scouty.rpc(), scouty.endpoint()).await?;
+    debug!("network {:?}", network);
+
+    // Try to run hooks for each event, in order of the filters matched
+    let mut hook_queues: Vec<Vec<HookCall>> = Vec::new();
+    for (chain_event, paths) in chain_events.iter() {
+        let mut args = HookArgs::new();
+        args.push("event", chain_event.name.to_string());
+        args.push("block_number", chain_event.block_number);
+        args.push("fields", chain_event.fields.clone());
+
+        push_network_args(&mut args, &network, &config);
+
+        hook_queues.push(
+            paths
+                .iter()
+                .map(|path| HookCall::new(HOOK_CHAIN_EVENT, path, args.clone()))
+                .collect(),
+        );
+    }
+
+    let hooks = Hook::run_queues(hook_queues).await;
+    let events: Vec<ChainEvent> = chain_events
+        .into_iter()
+        .zip(hooks)
+        .map(|((chain_event, _), hooks)| ChainEvent {
+            hooks,
+            ..chain_event
+        })
+        .collect();
+
+    // Prepare notification report with all events matched in the block
+    let data = RawData {
+        network,
+        events,
+        section: Section::Events,
+        ..Default::default()
+    };
+
+    Ok(Some(data))
+}
+
+async fn try_run_runtime_upgraded_hook(
+    scouty: &Scouty,
+    event: Option<CodeUpdated>,
+    block_number: u32,
+    block_hash: H256,
+) -> Result<Option<RawData>, ScoutyError> {
+    if event.is_some() {
+        let api = scouty.client().clone();
+        let config = CONFIG.clone();
+
+        // Fetch the upgraded runtime version and metadata
+        let old_spec_version = api.runtime_version().spec_version;
+        let new_version = scouty
+            .rpc()
+            .state_get_runtime_version(Some(block_hash))
+            .await?;
+        let old_metadata = api.metadata();
+        let new_metadata = scouty.rpc().state_get_metadata(Some(block_hash)).await?;
+
+        info!(
+            "Runtime upgraded from {} to {} at block #{}",
+            old_spec_version, new_version.spec_version, block_number
+        );
+
+        // Verify that storage entries and events used by scouty are still compatible
+        let incompatibilities = metadata_incompatibilities(&old_metadata, &new_metadata);
+        for incompatibility in incompatibilities.iter() {
+            warn!(
+                "{} in runtime {}, decoding might fail",
+                incompatibility, new_version.spec_version
+            );
+        }
+
+        // Update client so that the following blocks are decoded with the new runtime
+        update_runtime(scouty, block_hash).await?;
+
+        let network = Network::load(scouty.rpc(), scouty.endpoint()).await?;
+        debug!("network {:?}", network);
+
+        let mut args = HookArgs::new();
+        args.push("old_spec_version", old_spec_version);
+        args.push("new_spec_version", new_version.spec_version);
+        args.push("block_number", block_number);
+        args.push("is_compatible", incompatibilities.is_empty());
+
+        push_network_args(&mut args, &network, &config);
+
+        // Try run hook
+        let hooks = Hook::run(
+            HOOK_RUNTIME_UPGRADED,
+            &config.hook_runtime_upgraded_path,
+            args,
+        )
+        .await;
+
+        // Set runtime upgrade info
+        let runtime_upgrade = RuntimeUpgrade {
+            block_number,
+            old_spec_version,
+            new_spec_version: new_version.spec_version,
+            incompatibilities,
+            hooks,
+        };
+
+        // Prepare notification report
+        let data = RawData {
+            network,
+            runtime_upgrade,
+            section: Section::RuntimeUpgrade,
+            ..Default::default()
+        };
+
+        return Ok(Some(data));
+    }
+    Ok(None)
+}
+
+async fn try_run_session_hooks(
+    scouty: &Scouty,
+    event: Option<&NewSession>,
+    authority_records: &mut AuthorityRecords,
+    block_number: u32,
+    authority_index: Option<AuthorityIndex>,
+    para_records: &mut ParaRecords,
+) -> Result<Option<RawData>, ScoutyError> {
+    if let Some(event) = event {
+        // Collect session data
+        let data = SessionHookData::load(scouty, event.session_index).await?;
+        let session = &data.session;
+
+        // Authority records -->
+        // Set a new authority set every new era in authority_records
+        if (session.eras_session_index) == 1 {
+            // Get current active authorities
+            authority_records.set_authorities(data.active_validators.clone());
+        }
+        // Set a new session in authority_records
+        authority_records.set_session(session.current_session_index);
+        // Track authority record with the new session updated
+        authority_records.insert_record(block_number, authority_index)?;
+        // Authority records <--
+
+        // Para records -->
+        // Set a new validator index for config stashes every new era in para_records
+        if (session.eras_session_index) == 1 {
+            para_records.reset_config_stashes(data.active_validators.clone())?;
+        }
+        // Track para record on a new session
+        track_para_records(scouty, session.current_session_index, para_records).await?;
+        // Para records <--
+
+        let data = run_session_hooks(
+            scouty,
+            data,
+            block_number,
+            authority_records,
+            para_records,
+            session_hooks_due,
+        )
+        .await?;
+
+        return Ok(Some(data));
+    }
+    Ok(None)
+}
+
+/// Returns the hooks due for a validator stash on a new session
+fn session_hooks_due(session: &Session, v: &Validator) -> Vec<&'static str> {
+    let mut hooks = vec![HOOK_NEW_SESSION];
+    if session.eras_session_index == 1 {
+        hooks.push(HOOK_NEW_ERA);
+    }
+    if session.is_last_session_of_era() && session.queued_session_keys_changed {
+        // If stash is not active and keys are queued for next Era -> trigger hook to get ready and warm up
+        if !v.is_active && v.is_queued {
+            hooks.push(HOOK_VALIDATOR_STARTS_ACTIVE_NEXT_ERA);
+        }
+        // If stash is active and keys are not queued for next Era trigger hook to inform operator
+        if v.is_active && !v.is_queued {
+            hooks.push(HOOK_VALIDATOR_STARTS_INACTIVE_NEXT_ERA);
+        }
+    }
+    hooks
+}
+
+/// Run the session hooks returned by `hooks_due` for each config stash and
+/// returns the session notification
+async fn run_session_hooks<'a>(
+    scouty: &Scouty,
+    data: SessionHookData,
+    block_number: u32,
+    authority_records: &AuthorityRecords,
+    para_records: &ParaRecords,
+    hooks_due: impl Fn(&Session, &Validator) -> Vec<&'a str>,
+) -> Result<RawData, ScoutyError> {
+    let config = CONFIG.clone();
+    let session = &data.session;
+
+    // Collect validators info based on config stashes
+    let mut validators = collect_validators_data(scouty).await?;
+
+    // Try to run hooks for each stash
+    let mut hook_queues: Vec<Vec<HookCall>> = vec![Vec::new(); validators.len()];
+    for (i, v) in validators.iter_mut().enumerate() {
+        let config = config.for_stash(&v.stash);
+        let hooks = hooks_due(session, v);
+
+        // Try HOOK_NEW_SESSION
+        let mut args = data
+            .hook_args(
+                scouty,
+                v,
+                &config,
+                block_number,
+                authority_records,
+                para_records,
+            )
+            .await?;
+
+        if hooks.contains(&HOOK_NEW_SESSION) {
+            hook_queues[i].push(HookCall::new(
+                HOOK_NEW_SESSION,
+                &config.hook_new_session_path,
+                args.clone(),
+            ));
+        }
+
+        // Try HOOK_NEW_ERA
+        if hooks.contains(&HOOK_NEW_ERA) {
+            data.push_era_points_args(&mut args, v, &config);
+
+            hook_queues[i].push(HookCall::new(
+                HOOK_NEW_ERA,
+                &config.hook_new_era_path,
+                args.clone(),
+            ));
+        }
+
+        if hooks.contains(&HOOK_VALIDATOR_STARTS_ACTIVE_NEXT_ERA)
+            || hooks.contains(&HOOK_VALIDATOR_STARTS_INACTIVE_NEXT_ERA)
+        {
+            let next_era_index = session.active_era_index + 1;
+            let next_session_index = session.current_session_index + 1;
+            let mut args = HookArgs::new();
+            args.push("stash", convert_account_id(v.stash.clone()).to_string());
+            args.push("identity", v.name.to_string());
+            args.push(
+                "queued_session_keys",
+                format!("0x{:?}", HexDisplay::from(&v.queued_session_keys)),
+            );
+            args.push("next_era_index", next_era_index);
+            args.push("next_session_index", next_session_index);
+
+            push_network_args(&mut args, &data.network, &config);
+
+            // Try HOOK_VALIDATOR_STARTS_ACTIVE_NEXT_ERA
+            if hooks.contains(&HOOK_VALIDATOR_STARTS_ACTIVE_NEXT_ERA) {
+                hook_queues[i].push(HookCall::new(
+                    HOOK_VALIDATOR_STARTS_ACTIVE_NEXT_ERA,
+                    &config.hook_validator_starts_active_next_era_path,
+                    args.clone(),
+                ));
+            }
+
+            // Try HOOK_VALIDATOR_INACTIVE_NEXT_ERA
+            if hooks.contains(&HOOK_VALIDATOR_STARTS_INACTIVE_NEXT_ERA) {
+                hook_queues[i].push(HookCall::new(
+                    HOOK_VALIDATOR_STARTS_INACTIVE_NEXT_ERA,
+                    &config.hook_validator_starts_inactive_next_era_path,
+                    args.clone(),
+                ));
+            }
+        }
+    }
+
+    // Try run hooks, in order for each stash and concurrently across stashes
+    let hooks = Hook::run_queues(hook_queues).await;
+    for (v, hooks) in validators.iter_mut().zip(hooks) {
+        v.hooks.extend(hooks);
+    }
+
+    // Prepare notification report
+    debug!("validators {:?}", validators);
+
+    Ok(RawData {
+        network: data.network,
+        session: data.session,
+        validators,
+        section: Section::Session,
+        ..Default::default()
+    })
+}
+
+/// Run the scheduled hook if it is due, on a live block
+async fn try_run_scheduled_hook(
+    scouty: &Scouty,
+    checkpoint: &mut Checkpoint,
+    block_number: u32,
+) -> Result<(), ScoutyError> {
+    let config = CONFIG.clone();
+    if !config.any_stash(|c| !c.hook_scheduled_path.is_empty()) {
+        return Ok(());
+    }
+
+    // NOTE: the first run is only due after a full interval, since the init hook runs on start
+    let now = Utc::now();
+    match checkpoint.scheduled_at {
+        Some(scheduled_at) if now >= scheduled_at => (),
+        Some(_) => return Ok(()),
+        None => {
+            let scheduled_at = config.schedule().next_after(now);
+            info!("Scheduled hook due at {}", scheduled_at);
+            checkpoint.scheduled_at = Some(scheduled_at);
+            return Ok(());
+        }
+    }
+    checkpoint.scheduled_at = Some(config.schedule().next_after(now));
+
+    run_scheduled_hook(
+        scouty,
+        block_number,
+        &checkpoint.authority_records,
+        &checkpoint.para_records,
+    )
+    .await
+}
+
+async fn run_scheduled_hook(
+    scouty: &Scouty,
+    block_number: u32,
+    authority_records: &AuthorityRecords,
+    para_records: &ParaRecords,
+) -> Result<(), ScoutyError> {
+    let config = CONFIG.clone();
+
+    // Collect session data
+    let current_session_index_addr = storage::<u32>("Session", "CurrentIndex", vec![]);
+    let current_session_index = match scouty
+        .storage()
+        .await?
+        .fetch(&current_session_index_addr)
+        .await?
+    {
+        Some(index) => index,
+        None => return Err("Current session index not defined".into()),
+    };
+    let data = SessionHookData::load(scouty, current_session_index).await?;
+
+    // Collect validators info based on config stashes
+    let mut validators = collect_validators_data(scouty).await?;
+
+    // Try to run hooks for each stash
+    let mut hook_queues: Vec<Vec<HookCall>> = vec![Vec::new(); validators.len()];
+    for (i, v) in validators.iter_mut().enumerate() {
+        let config = config.for_stash(&v.stash);
+
+        // Try HOOK_SCHEDULED
+        let mut args = data
+            .hook_args(
+                scouty,
+                v,
+                &config,
+                block_number,
+                authority_records,
+                para_records,
+            )
+            .await?;
+        data.push_era_points_args(&mut args, v, &config);
+
+        hook_queues[i].push(HookCall::new(
+            HOOK_SCHEDULED,
+            &config.hook_scheduled_path,
+            args,
+        ));
+    }
+
+    // Try run hooks, in order for each stash and concurrently across stashes
+    let hooks = Hook::run_queues(hook_queues).await;
+    for (v, hooks) in validators.iter_mut().zip(hooks) {
+        v.hooks.extend(hooks);
+    }
+
+    // Prepare notification report
+    debug!("validators {:?}", validators);
+
+    let data = RawData {
+        init: Init { block_number },
+        network: data.network,
+        session: data.session,
+        validators,
+        section: Section::Scheduled,
+        ..Default::default()
+    };
+
+    scouty.notify(data).await?;
+
+    Ok(())
+}
+
+/// Chain data shared by every stash in the session hooks, also used by the scheduled hook
+struct SessionHookData {
+    session: Session,
+    network: Network,
+    active_validators: Vec<AccountId32>,
+    all_nominators_map: BTreeMap<String, Vec<(String, u128, u32)>>,
+    era_reward_points: EraRewardPoints,
+    era_reward: u128,
+}
+
+impl SessionHookData {
+    async fn load(scouty: &Scouty, session_index: u32) -> Result<Self, ScoutyError> {
+        let config = CONFIG.clone();
+
+        let session = collect_session_data(scouty, session_index).await?;
+
+        // Collect session active validators
+        let validators_addr =
+            storage::<Vec<AccountId32>>("Session", "Validators", vec![]);
+        let active_validators = scouty
+            .storage()
+            .await?
+            .fetch(&validators_addr)
+            .await?
+            .unwrap_or_default();
+
+        let network = Network::load(scouty.rpc(), scouty.endpoint()).await?;
+        debug!("network {:?}", network);
+
+        // Sync all nominators
+        let all_nominators_map =
+            if config.any_stash(|c| c.expose_all_nominators || c.expose_all) {
+                get_nominators(scouty).await?
+            } else {
+                BTreeMap::new()
+            };
+
+        // Fetch era reward points and reward from previous era
+        let (era_reward_points, era_reward) =
+            fetch_previous_era_rewards(scouty, session.active_era_index).await?;
+
+        Ok(Self {
+            session,
+            network,
+            active_validators,
+            all_nominators_map,
+            era_reward_points,
+            era_reward,
+        })
+    }
+
+    /// Returns the new session hook arguments of a validator stash
+    async fn hook_args(
+        &self,
+        scouty: &Scouty,
+        v: &Validator,
+        config: &Config,
+        block_number: u32,
+        authority_records: &AuthorityRecords,
+        para_records: &ParaRecords,
+    ) -> Result<HookArgs, ScoutyError> {
+        let session = &self.session;
+
+        let mut args = validator_args(v);
+        args.push("session.active_era_index", session.active_era_index);
+        args.push(
+            "session.current_session_index",
+            session.current_session_index,
+        );
+        args.push("session.eras_session_index", session.eras_session_index);
+        args.push("block_number", block_number);
+
+        push_network_args(&mut args, &self.network, config);
+
+        if v.is_active && (config.expose_nominators || config.expose_all) {
+            let (total_active_stake, own_stake, nominators, nominators_stake) =
+                get_active_nominators(scouty, session.active_era_index, &v.stash).await?;
+            // calculate APR
+            let apr = calculate_projected_apr(
+                scouty,
+                &v.stash,
+                self.network.token_decimals,
+                total_active_stake,
+                self.era_reward,
+                self.active_validators.len().try_into().unwrap(),
+            )
+            .await?;
+            args.push("nominators.apr", format!("{:.2}", apr * 100.0));
+            args.push("nominators.total_stake", total_active_stake.to_string());
+            args.push("nominators.own_stake", own_stake.to_string());
+            args.push("nominators.active_stashes", nominators);
+            args.push(
+                "nominators.active_stakes",
+                nominators_stake
+                    .iter()
+                    .map(|x| x.to_string())
+                    .collect::<Vec<String>>(),
+            );
+        } else {
+            args.skip(5);
+        }
+
+        if v.is_active
+            && scouty.features().babe
+            && (config.expose_authored_blocks || config.expose_all)
+        {
+            let previous_session_total =
+                authority_records.previous_session_total(&v.stash);
+            let previous_six_sessions_total =
+                authority_records.previous_six_sessions_total(&v.stash);
+            args.push("authored_blocks.previous_session", previous_session_total);
+            args.push(
+                "authored_blocks.previous_six_sessions",
+                previous_six_sessions_total,
+            );
+        } else {
+            args.skip(2);
+        }
+
+        if config.expose_all_nominators || config.expose_all {
+            if let Some(all_nominators) =
+                self.all_nominators_map.get(&v.stash.to_string())
+            {
+                let all_nominators_stashes = all_nominators
+                    .iter()
+                    .map(|(x, _, _)| x.to_string())
+                    .collect::<Vec<String>>();
+
+                let total_nominators_stake: u128 =
+                    all_nominators.iter().map(|(_, x, _)| x).sum();
+
+                let raw_nominees_stake: u128 =
+                    all_nominators.iter().map(|(_, x, y)| x / *y as u128).sum();
+                args.push("all_nominators.stashes", all_nominators_stashes);
+                args.push(
+                    "all_nominators.stake",
+                    vec![
+                        total_nominators_stake.to_string(),
+                        raw_nominees_stake.to_string(),
+                    ],
+                );
+            } else {
+                args.skip(2);
+            }
+        } else {
+            args.skip(2);
+        }
+
+        if v.is_active
+            && scouty.features().paras_shared
+            && (config.expose_para_validator || config.expose_all)
+        {
+            let is_para_validator = para_records.is_para_validator(&v.stash);
+            let previous_six_sessions_total =
+                para_records.previous_six_sessions_total(&v.stash);
+            args.push("para.is_para_validator", is_para_validator);
+            args.push(
+                "para.previous_six_sessions_total",
+                previous_six_sessions_total,
+            );
+        } else {
+            args.skip(2);
+        }
+
+        Ok(args)
+    }
+
+    /// Expose validator last era points
+    fn push_era_points_args(&self, args: &mut HookArgs, v: &Validator, config: &Config) {
+        if config.expose_era_points || config.expose_all {
+            let points =
+                get_validator_points_info(&v.stash, self.era_reward_points.clone());
+            args.push("era_points.validator", points.validator);
+            args.push("era_points.era_avg", points.era_avg as u32);
+        } else {
+            args.skip(2);
+        }
+    }
+}
+
+async fn get_active_nominators(
+    scouty: &Scouty,
+    era_index: u32,
+    stash: &AccountId32,
+) -> Result<(u128, u128, Vec<String>, Vec<u128>), ScoutyError> {
+    let mut exposure_total = 0;
+    let mut exposure_own = 0;
+    let mut nominators: Vec<String> = vec![];
+    let mut nominators_stake: Vec<u128> = vec![];
+
+    let eras_stakers_paged_addr = storage::<ExposurePage>(
+        "Staking",
+        "ErasStakersPaged",
+        vec![
+            make_static_storage_map_key(era_index),
+            make_static_storage_map_key(stash),
+        ],
+    );
+    let mut iter = scouty
+        .storage()
+        .await?
+        .iter(eras_stakers_paged_addr)
+        .await?;
+
+    while let Some(Ok((_, exposure))) = iter.next().await {
+        debug!("__exposure: {:?}", exposure);
+        for other in exposure.others {
+            // NOTE: convert nominator account to specific chain format
+            nominators.push(convert_account_id(other.who).to_string());
+            nominators_stake.push(other.value);
+        }
+    }
+
+    let eras_stakers_overview_addr = storage::<PagedExposureMetadata>(
+        "Staking",
+        "ErasStakersOverview",
+        vec![
+            make_static_storage_map_key(era_index),
+            make_static_storage_map_key(stash),
+        ],
+    );
+    if let Some(exposure) = scouty
+        .storage()
+        .await?
+        .fetch(&eras_stakers_overview_addr)
+        .await?
+    {
+        debug!("__exposure: {:?}", exposure);
+        exposure_total = exposure.total;
+        exposure_own = exposure.own;
+    }
+
+    Ok((exposure_total, exposure_own, nominators, nominators_stake))
+}
+
+async fn get_nominators(
+    scouty: &Scouty,
+) -> Result<BTreeMap<String, Vec<(String, u128, u32)>>, ScoutyError> {
+    let config = CONFIG.clone();
+
+    // BTreeMap<String, Vec<(String, u128, u32)>> = validator_stash : [(nominator_stash, nominator_total_stake, number_of_nominations)]
+    let mut stashes_nominators: BTreeMap<String, Vec<(String, u128, u32)>> =
+        BTreeMap::new();
+    for stash_str in config.stashes.iter() {
+        let stash = AccountId32::from_str(stash_str).map_err(|e| {
+            ScoutyError::Other(format!(
+                "Invalid SS58 format account: {:?} error: {e:?}",
+                stash_str
+            ))
+        })?;
+        stashes_nominators.insert(stash.to_string(), vec![]);
+    }
+
+    info!("Starting syncing all nominators");
+    let storage_query = storage::<Nominations>("Staking", "Nominators", vec![]);
+    let mut results = scouty.storage().await?.iter(storage_query).await?;
+    while let Some(Ok((key, nominations))) = results.next().await {
+        let nominator_stash = get_account_id_from_storage_key(key);
+        let bonded_addr = storage::<AccountId32>(
+            "Staking",
+            "Bonded",
+            vec![make_static_storage_map_key(&nominator_stash)],
+        );
+        if let Some(controller) = scouty.storage().await?.fetch(&bonded_addr).await? {
+            let ledger_addr = storage::<StakingLedger>(
+                "Staking",
+                "Ledger",
+                vec![make_static_storage_map_key(&controller)],
+            );
+            let total_nominator_stake = if let Some(ledger) =
+                scouty.storage().await?.fetch(&ledger_addr).await?
+            {
+                ledger.total
+            } else {
+                0
+            };
+
+            for stash_str in config.stashes.iter() {
+                let stash = AccountId32::from_str(stash_str).map_err(|e| {
+                    ScoutyError::Other(format!(
+                        "Invalid SS58 format account: {:?} error: {e:?}",
+                        stash_str
+                    ))
+                })?;
+                if nominations.targets.contains(&stash) {
+                    if let Some(x) = stashes_nominators.get_mut(&stash.to_string()) {
+                        // NOTE: convert account to chain specific format
+                        x.push((
+                            convert_account_id(nominator_stash.clone()).to_string(),
+                            total_nominator_stake,
+                            nominations.targets.len().try_into().unwrap(),
+                        ));
+                    }
+                }
+            }
+        }
+    }
+    info!("Finished syncing all nominators");
+    Ok(stashes_nominators)
+}
+
+async fn collect_session_data(
+    scouty: &Scouty,
+    _session_index: u32,
+) -> Result<Session, ScoutyError> {
+    // Get Era index
+    let active_era_index_addr = storage::<ActiveEraInfo>("Staking", "ActiveEra", vec![]);
+    let active_era_index = match scouty
+        .storage()
+        .await?
+        .fetch(&active_era_index_addr)
+        .await?
+    {
+        Some(info) => info.index,
+        None => return Err("Current era index not defined".into()),
+    };
+
+    // Get current session
+    let current_session_index_addr = storage::<u32>("Session", "CurrentIndex", vec![]);
+    let current_session_index = scouty
+        .storage()
+        .await?
+        .fetch(&current_session_index_addr)
+        .await?
+        .ok_or("Current session index not defined")?;
+
+    // Get start session index
+    let start_session_index_addr = storage::<u32>(
+        "Staking",
+        "ErasStartSessionIndex",
+        vec![make_static_storage_map_key(active_era_index)],
+    );
+    let start_session_index = scouty
+        .storage()
+        .await?
+        .fetch(&start_session_index_addr)
+        .await?
+        .ok_or("Era start session index not defined")?;
+
+    // Eras session index
+    let eras_session_index = 1 + current_session_index - start_session_index;
+
+    // Get session keys queued status
+    let queued_changed_addr = storage::<bool>("Session", "QueuedChanged", vec![]);
+    let queued_session_keys_changed = scouty
+        .storage()
+        .await?
+        .fetch(&queued_changed_addr)
+        .await?
+        .unwrap_or_default();
+
+    // Set network info
+    let session = Session {
+        active_era_index,
+        current_session_index,
+        eras_session_index,
+        queued_session_keys_changed,
+        sessions_per_era: scouty.features().sessions_per_era,
+    };
+    debug!("session {:?}", session);
+
+    Ok(session)
+}
+
+async fn collect_validators_data(scouty: &Scouty) -> Result<Validators, ScoutyError> {
+    let config = CONFIG.clone();
+
+    // Verify session active validators
+    let validators_addr = storage::<Vec<AccountId32>>("Session", "Validators", vec![]);
+    let active_validators = scouty
+        .storage()
+        .await?
+        .fetch(&validators_addr)
+        .await?
+        .unwrap_or_default();
+
+    // Verify session queued keys
+    let queued_keys_addr =
+        storage::<Vec<(AccountId32, SessionKeys)>>("Session", "QueuedKeys", vec![]);
+    let queued_keys = scouty
+        .storage()
+        .await?
+        .fetch(&queued_keys_addr)
+        .await?
+        .unwrap_or_default();
+
+    let mut validators: Validators = Vec::new();
+    for stash_str in config.stashes.iter() {
+        let stash = AccountId32::from_str(stash_str).map_err(|e| {
+            ScoutyError::Other(format!(
+                "Invalid SS58 format account: {:?} error: {e:?}",
+                stash_str
+            ))
+        })?;
+        let mut v = Validator::new(stash.clone());
+
+        // Get validator name
+        v.name = get_display_name(scouty, &stash).await?;
+
+        // Set validator label and tags from the stash settings
+        if let Some(stash_config) = config.stash_config(&stash) {
+            v.label = stash_config.label.clone();
+            v.tags = stash_config.tags.clone();
+        }
+
+        // Check if validator is in active set
+        v.is_active = active_validators.contains(&v.stash);
+
+        // Check if validator session key is queued
+        for (account_id, session_keys) in &queued_keys {
+            if account_id == &v.stash {
+                v.is_queued = true;
+                v.queued_session_keys = value_to_bytes(session_keys);
+                break;
+            }
+        }
+
+        validators.push(v);
+    }
+
+    debug!("validators {:?}", validators);
+    Ok(validators)
+}
+
+async fn get_display_name(
+    scouty: &Scouty,
+    stash: &AccountId32,
+) -> Result<String, ScoutyError> {
+    let identity = if scouty.features().identity {
+        get_identity(scouty, stash, None).await?
+    } else {
+        None
+    };
+    if let Some(identity) = identity {
+        Ok(identity.to_string())
+    } else {
+        let s = &stash.to_string();
+        Ok(format!("{}...{}", &s[..6], &s[s.len() - 6..]))
+    }
+}
+
+#[async_recursion]
+async fn get_identity(
+    scouty: &Scouty,
+    stash: &AccountId32,
+    sub_account_name: Option<String>,
+) -> Result<Option<Identity>, ScoutyError> {
+    let identity_of_addr = storage::<DecodedValue>(
+        "Identity",
+        "IdentityOf",
+        vec![make_static_storage_map_key(stash)],
+    );
+    match scouty.storage().await?.fetch(&identity_of_addr).await? {
+        Some(registration) => {
+            debug!("identity {:?}", registration);
+            // NOTE: Since identity pallet v2 the registration is stored together
+            // with the username as a tuple (Registration, Option<Username>)
+            let display = registration
+                .at("info")
+                .or_else(|| registration.at(0).and_then(|r| r.at("info")))
+                .and_then(|info| info.at("display"));
+            let parent = match display {
+                Some(data) => parse_identity_data(data),
+                None => return Ok(None),
+            };
+            let identity = match sub_account_name {
+                Some(child) => Identity::with_name_and_sub(parent, child),
+                None => Identity::with_name(parent),
+            };
+            Ok(Some(identity))
+        }
+        None => {
+            let super_of_addr = storage::<(AccountId32, IdentityData)>(
+                "Identity",
+                "SuperOf",
+                vec![make_static_storage_map_key(stash)],
+            );
+            if let Some((parent_account, data)) =
+                scouty.storage().await?.fetch(&super_of_addr).await?
+            {
+                let sub_account_name = parse_identity_data(&data);
+                get_identity(scouty, &parent_account, Some(sub_account_name)).await
+            } else {
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// Parse identity `Data` by variant name, only raw data is displayable
+fn parse_identity_data<T>(data: &Value<T>) -> String {
+    match &data.value {
+        ValueDef::Variant(variant) if variant.name.starts_with("Raw") => {
+            String::from_utf8_lossy(&value_to_bytes(data)).to_string()
+        }
+        ValueDef::Variant(variant) if variant.name == "None" => String::new(),
+        _ => "???".to_string(),
+    }
+}
+
+async fn init_authority_records(
+    scouty: &Scouty,
+    authority_records: &mut AuthorityRecords,
+) -> Result<(), ScoutyError> {
+    // Get current block
+    let number_addr = storage::<u32>("System", "Number", vec![]);
+    let number = scouty
+        .storage()
+        .await?
+        .fetch(&number_addr)
+        .await?
+        .ok_or("Block number not defined")?;
+
+    authority_records.set_block(number);
+
+    // Get current session
+    let current_session_index_addr = storage::<u32>("Session", "CurrentIndex", vec![]);
+    let current_session_index = scouty
+        .storage()
+        .await?
+        .fetch(&current_session_index_addr)
+        .await?
+        .ok_or("Current session index not defined")?;
+
+    authority_records.set_session(current_session_index);
+
+    // Get current active authorities
+    let validators_addr = storage::<Vec<AccountId32>>("Session", "Validators", vec![]);
+    let active_validators = scouty
+        .storage()
+        .await?
+        .fetch(&validators_addr)
+        .await?
+        .unwrap_or_default();
+
+    authority_records.set_authorities(active_validators);
+
+    Ok(())
+}
+
+async fn init_para_records(
+    scouty: &Scouty,
+    para_records: &mut ParaRecords,
+) -> Result<(), ScoutyError> {
+    // Get current active authorities
+    let validators_addr = storage::<Vec<AccountId32>>("Session", "Validators", vec![]);
+    let active_validators = scouty
+        .storage()
+        .await?
+        .fetch(&validators_addr)
+        .await?
+        .unwrap_or_default();
+
+    para_records.reset_config_stashes(active_validators)?;
+
+    // Get current session
+    let current_session_index_addr = storage::<u32>("Session", "CurrentIndex", vec![]);
+    let current_session_index = scouty
+        .storage()
+        .await?
+        .fetch(&current_session_index_addr)
+        .await?
+        .ok_or("Current session index not defined")?;
+
+    track_para_records(scouty, current_session_index, para_records).await?;
+
+    Ok(())
+}
+
+async fn track_para_records(
+    scouty: &Scouty,
+    new_session_index: u32,
+    para_records: &mut ParaRecords,
+) -> Result<(), ScoutyError> {
+    if !scouty.features().paras_shared {
+        return Ok(());
+    }
+
+    // Get para active validator indices
+    let para_validators_addr =
+        storage::<Vec<u32>>("ParasShared", "ActiveValidatorIndices", vec![]);
+    let active_validator_indices = scouty
+        .storage()
+        .await?
+        .fetch(&para_validators_addr)
+        .await?
+        .unwrap_or_default();
+
+    // Insert record
+    para_records.insert_record(new_session_index, active_validator_indices);
+
+    Ok(())
+}
+
+/// Returns the reward points and the reward of the era before the active one, empty
+/// points and no reward if there is no previous era or it has not been recorded
+async fn fetch_previous_era_rewards(
+    scouty: &Scouty,
+    active_era_index: u32,
+) -> Result<(EraRewardPoints, u128), ScoutyError> {
+    let era_index = match active_era_index.checked_sub(1) {
+        Some(era_index) => era_index,
+        None => return Ok((EraRewardPoints::default(), 0)),
+    };
+
+    let era_reward_points_addr = storage::<EraRewardPoints>(
+        "Staking",
+        "ErasRewardPoints",
+        vec![make_static_storage_map_key(era_index)],
+    );
+    let era_reward_points = scouty
+        .storage()
+        .await?
+        .fetch(&era_reward_points_addr)
+        .await?
+        .unwrap_or_default();
+
+    let era_reward_addr = storage::<u128>(
+        "Staking",
+        "ErasValidatorReward",
+        vec![make_static_storage_map_key(era_index)],
+    );
+    let era_reward = scouty
+        .storage()
+        .await?
+        .fetch(&era_reward_addr)
+        .await?
+        .unwrap_or_default();
+
+    Ok((era_reward_points, era_reward))
+}
+
+fn get_validator_points_info(
+    stash: &AccountId32,
+    era_reward_points: EraRewardPoints,
+) -> Points {
+    let stash_points = match era_reward_points
+        .individual
+        .iter()
+        .find(|(s, _)| s == stash)
+    {
+        Some((_, p)) => *p,
+        None => 0,
+    };
+
+    // Calculate average points
+    let points_f64: Vec<f64> = era_reward_points
+        .individual
+        .into_iter()
+        .map(|(_, points)| points as f64)
+        .collect();
+
+    Points {
+        validator: stash_points,
+        era_avg: stats::mean(&points_f64),
+    }
+}
+
+async fn calculate_projected_apr(
+    scouty: &Scouty,
+    stash: &AccountId32,
+    token_decimals: u8,
+    stash_active_stake: u128,
+    era_reward: u128,
+    total_active_validators: u32,
+) -> Result<f64, ScoutyError> {
+    // Get validator prefs
+    let validator_prefs_addr = storage::<ValidatorPrefs>(
+        "Staking",
+        "Validators",
+        vec![make_static_storage_map_key(stash)],
+    );
+    let validator_prefs = scouty
+        .storage()
+        .await?
+        .fetch(&validator_prefs_addr)
+        .await?
+        .ok_or("Validator preferences not defined")?;
+
+    let commission = normalize_commission(validator_prefs.commission);
+
+    let avg_reward_per_validator_per_era =
+        from_plancks_to_ksm(token_decimals, era_reward) / total_active_validators as f64;
+
+    let nominators_reward = (1.0 - commission) * avg_reward_per_validator_per_era;
+    let nominator_reward_per_ksm = (1.0_f64
+        / from_plancks_to_ksm(token_decimals, stash_active_stake))
+        * nominators_reward;
+    let apr = nominator_reward_per_ksm * scouty.eras_per_day() as f64 * 365.0_f64;
+    Ok(apr)
+}
+
+/// Normalize commission perbill between 0 - 1
+fn normalize_commission(commission: u32) -> f64 {
+    commission as f64 / 10.0_f64.powi(9)
+}
+
+/// Convert Planks to KSM
+fn from_plancks_to_ksm(token_decimals: u8, plancks: u128) -> f64 {
+    plancks as f64 / 10.0_f64.powi(token_decimals.into())
+}
+
+async fn get_authority_index(
+    scouty: &Scouty,
+    block_hash: Option<H256>,
+) -> Result<Option<AuthorityIndex>, ScoutyError> {
+    if let Some(header) = scouty.rpc().chain_get_header(block_hash).await? {
+        let Digest { logs } = header.digest;
+        for digests in logs.iter() {
+            if let DigestItem::PreRuntime(_, data) = digests {
+                if let Ok(pre) = PreDigest::decode(&mut &data[..]) {
+                    return Ok(Some(pre.authority_index()));
+                }
+            }
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::Report;
+    use crate::runtimes::{kusama, polkadot, westend};
+
+    #[test]
+    fn it_finds_storage_entries_and_events_in_metadata() {
+        for bytes in [polkadot::METADATA, kusama::METADATA, westend::METADATA] {
+            let metadata = Metadata::decode(&mut &bytes[..]).unwrap();
+            for (pallet_name, entry_name) in STORAGE_ENTRIES {
+                assert!(metadata
+                    .pallet_by_name(pallet_name)
+                    .and_then(|pallet| pallet.storage_hash(entry_name))
+                    .is_some());
+            }
+            for (pallet_name, event_name) in EVENTS {
+                assert!(event_hash(&metadata, pallet_name, event_name).is_some());
+            }
+        }
+    }
+
+    #[test]
+    fn it_detects_metadata_incompatibilities() {
+        let polkadot = Metadata::decode(&mut &polkadot::METADATA[..]).unwrap();
+        assert!(metadata_incompatibilities(&polkadot, &polkadot).is_empty());
+
+        // Remove Referenda pallet to simulate a runtime upgrade without it
+        let mut upgraded =
+            subxt::metadata::types::Metadata::decode(&mut &polkadot::METADATA[..])
+                .unwrap();
+        upgraded.retain(|pallet_name| pallet_name != "Referenda", |_| true);
+        let upgraded = Metadata::from(upgraded);
+        assert_eq!(
+            metadata_incompatibilities(&polkadot, &upgraded),
+            vec!["Event Referenda::Submitted is not available".to_string()]
+        );
+    }
+
+    #[test]
+    fn it_groups_every_slash_in_a_block_in_one_report() {
+        let alice =
+            AccountId32::from_str("5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY")
+                .unwrap();
+        let bob =
+            AccountId32::from_str("5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty")
+                .unwrap();
+        let events = vec![
+            Slashed {
+                staker: alice.clone(),
+                amount: 10,
+            },
+            Slashed {
+                staker: bob.clone(),
+                amount: 20,
+            },
+            Slashed {
+                staker: alice.clone(),
+                amount: 5,
+            },
+        ];
+        let mut validators = vec![Validator::new(alice), Validator::new(bob)];
+        let network = Network {
+            name: "Polkadot".to_string(),
+            ..Default::default()
+        };
+
+        let (slashes, hook_queues) =
+            prepare_slashes(events, &mut validators, &network, &CONFIG);
+        assert_eq!(slashes.len(), 3);
+        assert_eq!(hook_queues.len(), 3);
+        assert!(validators.iter().all(|v| v.is_slashed));
+
+        let data = RawData {
+            network,
+            validators,
+            slashes,
+            section: Section::Slash,
+            ..Default::default()
+        };
+        let routes = data.route();
+        assert_eq!(routes.len(), 1);
+
+        let report = Report::from(routes.into_iter().next().unwrap().1);
+        assert!(report.message().contains("3 slashes occurred!"));
+        assert_eq!(report.cards().len(), 2);
+    }
+
+    #[test]
+    fn it_evaluates_offline_signals_without_parachains() {
+        let stash =
+            AccountId32::from_str("5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY")
+                .unwrap();
+        // NOTE: para records are never tracked on chains without ParasShared
+        let para_records = ParaRecords::new();
+        let signals = |authored_blocks: Option<u32>| {
+            offline_signals(
+                false,
+                authored_blocks,
+                para_records.was_para_validator(&stash),
+                para_records.previous_session_bitfields(&stash),
+                false,
+            )
+        };
+        assert_eq!(signals(Some(0)), vec!["no_authored_blocks"]);
+        assert!(signals(Some(3)).is_empty());
+        assert!(signals(None).is_empty());
+    }
+
+    #[test]
+    fn it_catches_up_on_missed_blocks_within_the_limit() {
+        let mut checkpoint = Checkpoint::default();
+        assert_eq!(checkpoint.catch_up(100, 10), CatchUp::None);
+
+        checkpoint.advance(100);
+        assert_eq!(checkpoint.catch_up(99, 10), CatchUp::Processed);
+        assert_eq!(checkpoint.catch_up(100, 10), CatchUp::Processed);
+        assert_eq!(checkpoint.catch_up(101, 10), CatchUp::None);
+        assert_eq!(checkpoint.catch_up(111, 10), CatchUp::Blocks(101, 110));
+        assert_eq!(checkpoint.catch_up(112, 10), CatchUp::Exceeded(101, 111));
+
+        // Missed blocks move the checkpoint forward one by one, before the live block
+        for block_number in 101..=110 {
+            checkpoint.advance(block_number);
+            assert_eq!(checkpoint.last_block_number, Some(block_number));
+        }
+        checkpoint.advance(111);
+        assert_eq!(checkpoint.catch_up(111, 10), CatchUp::Processed);
+
+        // The checkpoint never moves backwards
+        checkpoint.advance(105);
+        assert_eq!(checkpoint.last_block_number, Some(111));
+    }
+
+    #[cfg(unix)]
+    #[async_std::test]
+    async fn it_keeps_failed_hooks_in_the_block_report() {
+        use std::{fs, os::unix::fs::PermissionsExt};
+
+        let dirname = std::env::temp_dir().join("scouty_failed_hooks");
+        let _ = fs::remove_dir_all(&dirname);
+        fs::create_dir_all(&dirname).unwrap();
+        let mut filenames = Vec::new();
+        for (filename, content) in [
+            (
+                "_failing.sh",
+                "#!/bin/sh\necho node unreachable >&2\nexit 3\n",
+            ),
+            ("_new_session.sh", "#!/bin/sh\necho '! all good'\n"),
+        ] {
+            let path = dirname.join(filename);
+            fs::write(&path, content).unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+            filenames.push(path.to_string_lossy().to_string());
+        }
+
+        let mut validators = vec![
+            Validator::new(
+                AccountId32::from_str("5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY")
+                    .unwrap(),
+            ),
+            Validator::new(
+                AccountId32::from_str("5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty")
+                    .unwrap(),
+            ),
+        ];
+        let hook_queues = filenames
+            .iter()
+            .map(|filename| {
+                vec![HookCall::new(HOOK_NEW_SESSION, filename, HookArgs::new())]
+            })
+            .collect();
+
+        // A failing hook is recorded in the validator and does not stop the other stashes
+        let hooks = Hook::run_queues(hook_queues).await;
+        for (v, hooks) in validators.iter_mut().zip(hooks) {
+            v.hooks.extend(hooks);
+        }
+        assert!(validators[0].hooks[0].has_failed());
+        assert_eq!(validators[0].hooks[0].retries, CONFIG.hook_retries);
+        assert!(!validators[1].hooks[0].has_failed());
+
+        let data = RawData {
+            validators,
+            section: Section::Session,
+            ..Default::default()
+        };
+        let message = Report::from(data).message();
+        assert!(message.contains("Failed with exit code 3"));
+        assert!(message.contains("node unreachable"));
+        assert!(message.contains("all good"));
+        fs::remove_dir_all(&dirname).unwrap();
+    }
+}
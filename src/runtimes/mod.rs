@@ -19,9 +19,9 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-#![allow(clippy::all)]
-
+pub mod generic;
 pub mod kusama;
 pub mod polkadot;
 pub mod support;
-// pub mod westend;
+pub mod types;
+pub mod westend;
@@ -19,14 +19,45 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
+use crate::runtimes::{kusama, polkadot, westend};
+use codec::Decode;
+use subxt::Metadata;
+
 pub type ChainPrefix = u16;
 pub type ChainTokenSymbol = String;
 
+/// Number of sessions in an era if not defined by the runtime
+const DEFAULT_SESSIONS_PER_ERA: u32 = 6;
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum SupportedRuntime {
     Polkadot,
     Kusama,
     Westend,
+    /// Any other substrate chain, with features discovered from its metadata
+    Generic,
+}
+
+impl SupportedRuntime {
+    /// Returns the runtime metadata scouty was built and verified against
+    pub fn metadata(&self) -> Option<&'static [u8]> {
+        match self {
+            Self::Polkadot => Some(polkadot::METADATA),
+            Self::Kusama => Some(kusama::METADATA),
+            Self::Westend => Some(westend::METADATA),
+            Self::Generic => None,
+        }
+    }
+
+    /// Returns the number of eras in a day, if known for the runtime
+    pub fn eras_per_day(&self) -> Option<u32> {
+        match self {
+            Self::Polkadot => Some(polkadot::ERAS_PER_DAY),
+            Self::Kusama => Some(kusama::ERAS_PER_DAY),
+            Self::Westend => Some(westend::ERAS_PER_DAY),
+            Self::Generic => None,
+        }
+    }
 }
 
 impl From<ChainPrefix> for SupportedRuntime {
@@ -35,7 +66,7 @@ impl From<ChainPrefix> for SupportedRuntime {
             0 => Self::Polkadot,
             2 => Self::Kusama,
             42 => Self::Westend,
-            _ => unimplemented!("Chain prefix not supported"),
+            _ => Self::Generic,
         }
     }
 }
@@ -46,7 +77,7 @@ impl From<ChainTokenSymbol> for SupportedRuntime {
             "DOT" => Self::Polkadot,
             "KSM" => Self::Kusama,
             "WND" => Self::Westend,
-            _ => unimplemented!("Chain unit not supported"),
+            _ => Self::Generic,
         }
     }
 }
@@ -57,6 +88,116 @@ impl std::fmt::Display for SupportedRuntime {
             Self::Polkadot => write!(f, "Polkadot"),
             Self::Kusama => write!(f, "Kusama"),
             Self::Westend => write!(f, "Westend"),
+            Self::Generic => write!(f, "Substrate"),
+        }
+    }
+}
+
+/// Pallets available in the connected runtime, discovered from its metadata
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RuntimeFeatures {
+    pub session: bool,
+    pub staking: bool,
+    pub babe: bool,
+    pub identity: bool,
+    pub paras_shared: bool,
+    pub referenda: bool,
+    pub sessions_per_era: u32,
+}
+
+impl RuntimeFeatures {
+    /// Returns true if the pallets required by scouty are available
+    pub fn is_supported(&self) -> bool {
+        self.session && self.staking
+    }
+
+    /// Estimate the number of eras in a day from the runtime constants
+    pub fn eras_per_day(metadata: &Metadata) -> Option<u32> {
+        let epoch_duration: u64 = constant(metadata, "Babe", "EpochDuration")?;
+        let block_time: u64 = constant(metadata, "Babe", "ExpectedBlockTime")?;
+        let sessions_per_era: u32 = constant(metadata, "Staking", "SessionsPerEra")?;
+        let era_duration = epoch_duration * block_time * sessions_per_era as u64;
+        if era_duration == 0 {
+            return None;
+        }
+        Some(std::cmp::max(1, 86_400_000 / era_duration) as u32)
+    }
+}
+
+impl From<&Metadata> for RuntimeFeatures {
+    fn from(metadata: &Metadata) -> Self {
+        let has_pallet = |name: &str| metadata.pallet_by_name(name).is_some();
+        Self {
+            session: has_pallet("Session"),
+            staking: has_pallet("Staking"),
+            babe: has_pallet("Babe"),
+            identity: has_pallet("Identity"),
+            paras_shared: has_pallet("ParasShared"),
+            referenda: has_pallet("Referenda"),
+            sessions_per_era: constant(metadata, "Staking", "SessionsPerEra")
+                .unwrap_or(DEFAULT_SESSIONS_PER_ERA),
         }
     }
 }
+
+/// Decode a runtime constant from the metadata
+fn constant<T: Decode>(
+    metadata: &Metadata,
+    pallet_name: &str,
+    constant_name: &str,
+) -> Option<T> {
+    let constant = metadata
+        .pallet_by_name(pallet_name)?
+        .constant_by_name(constant_name)?;
+    T::decode(&mut constant.value()).ok()
+}
+
+impl std::fmt::Display for RuntimeFeatures {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let features = [
+            ("session", self.session),
+            ("staking", self.staking),
+            ("babe", self.babe),
+            ("identity", self.identity),
+            ("paras_shared", self.paras_shared),
+            ("referenda", self.referenda),
+        ];
+        let enabled: Vec<&str> = features
+            .iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(name, _)| *name)
+            .collect();
+        write!(f, "{}", enabled.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_falls_back_to_generic_runtime() {
+        assert_eq!(
+            SupportedRuntime::from("DOT".to_string()),
+            SupportedRuntime::Polkadot
+        );
+        assert_eq!(
+            SupportedRuntime::from("PAS".to_string()),
+            SupportedRuntime::Generic
+        );
+        assert_eq!(SupportedRuntime::from(7_u16), SupportedRuntime::Generic);
+    }
+
+    #[test]
+    fn it_discovers_features_from_metadata() {
+        let metadata = Metadata::decode(&mut &polkadot::METADATA[..]).unwrap();
+        let features = RuntimeFeatures::from(&metadata);
+        assert!(features.is_supported());
+        assert!(features.babe && features.identity && features.paras_shared);
+        assert_eq!(RuntimeFeatures::eras_per_day(&metadata), Some(1));
+        assert_eq!(features.sessions_per_era, 6);
+
+        let metadata = Metadata::decode(&mut &kusama::METADATA[..]).unwrap();
+        assert_eq!(RuntimeFeatures::eras_per_day(&metadata), Some(4));
+    }
+}
@@ -0,0 +1,429 @@
+// The MIT License (MIT)
+// Copyright © 2021 Aukbit Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+// Chain agnostic representations of the storage items and events used by scouty.
+//
+// Types are decoded by field name against the live runtime metadata, which means
+// that only the fields scouty cares about need to be declared here and that the
+// same types can be used across Polkadot, Kusama, Westend or any other chain
+// sharing the same pallets.
+//
+use codec::Decode;
+use scale_info::{form::PortableForm, PortableRegistry, Type, TypeDef, TypeDefPrimitive};
+use serde_json::{json, Map, Value as JsonValue};
+use std::convert::{TryFrom, TryInto};
+use subxt::{
+    blocks::StaticExtrinsic,
+    dynamic::DecodedValue,
+    events::StaticEvent,
+    ext::{
+        scale_decode::DecodeAsType,
+        scale_value::{Composite, Primitive, ValueDef},
+        sp_runtime,
+    },
+    utils::AccountId32,
+};
+
+#[derive(Debug, DecodeAsType)]
+#[decode_as_type(crate_path = "subxt::ext::scale_decode")]
+pub struct ActiveEraInfo {
+    pub index: u32,
+}
+
+#[derive(Debug, Clone, Default, DecodeAsType)]
+#[decode_as_type(crate_path = "subxt::ext::scale_decode")]
+pub struct EraRewardPoints {
+    pub individual: Vec<(AccountId32, u32)>,
+}
+
+#[derive(Debug, DecodeAsType)]
+#[decode_as_type(crate_path = "subxt::ext::scale_decode")]
+pub struct IndividualExposure {
+    pub who: AccountId32,
+    pub value: u128,
+}
+
+#[derive(Debug, DecodeAsType)]
+#[decode_as_type(crate_path = "subxt::ext::scale_decode")]
+pub struct ExposurePage {
+    pub others: Vec<IndividualExposure>,
+}
+
+#[derive(Debug, DecodeAsType)]
+#[decode_as_type(crate_path = "subxt::ext::scale_decode")]
+pub struct PagedExposureMetadata {
+    pub total: u128,
+    pub own: u128,
+}
+
+#[derive(Debug, DecodeAsType)]
+#[decode_as_type(crate_path = "subxt::ext::scale_decode")]
+pub struct Nominations {
+    pub targets: Vec<AccountId32>,
+}
+
+#[derive(Debug, DecodeAsType)]
+#[decode_as_type(crate_path = "subxt::ext::scale_decode")]
+pub struct StakingLedger {
+    pub total: u128,
+}
+
+#[derive(Debug, DecodeAsType)]
+#[decode_as_type(crate_path = "subxt::ext::scale_decode")]
+pub struct ValidatorPrefs {
+    pub commission: u32,
+}
+
+/// Session keys are runtime specific, so they are kept as a dynamic value
+/// and only converted into raw bytes when needed
+pub type SessionKeys = DecodedValue;
+
+/// Identity `Data` differs between identity pallet versions, so it is kept as a
+/// dynamic value and parsed by variant name
+pub type IdentityData = DecodedValue;
+
+/// Disabled validators are stored either as a validator index or as a tuple of
+/// validator index and offence severity, depending on the runtime version
+pub type DisabledValidator = DecodedValue;
+
+// Events
+
+#[derive(Debug, DecodeAsType)]
+#[decode_as_type(crate_path = "subxt::ext::scale_decode")]
+pub struct NewSession {
+    pub session_index: u32,
+}
+
+impl StaticEvent for NewSession {
+    const PALLET: &'static str = "Session";
+    const EVENT: &'static str = "NewSession";
+}
+
+#[derive(Debug, DecodeAsType)]
+#[decode_as_type(crate_path = "subxt::ext::scale_decode")]
+pub struct Slashed {
+    pub staker: AccountId32,
+    pub amount: u128,
+}
+
+impl StaticEvent for Slashed {
+    const PALLET: &'static str = "Staking";
+    const EVENT: &'static str = "Slashed";
+}
+
+#[derive(Debug, DecodeAsType)]
+#[decode_as_type(crate_path = "subxt::ext::scale_decode")]
+pub struct Chilled {
+    pub stash: AccountId32,
+}
+
+impl StaticEvent for Chilled {
+    const PALLET: &'static str = "Staking";
+    const EVENT: &'static str = "Chilled";
+}
+
+#[derive(Debug, DecodeAsType)]
+#[decode_as_type(crate_path = "subxt::ext::scale_decode")]
+pub struct Submitted {
+    pub index: u32,
+    pub track: u16,
+}
+
+impl StaticEvent for Submitted {
+    const PALLET: &'static str = "Referenda";
+    const EVENT: &'static str = "Submitted";
+}
+
+#[derive(Debug, DecodeAsType)]
+#[decode_as_type(crate_path = "subxt::ext::scale_decode")]
+pub struct CodeUpdated;
+
+impl StaticEvent for CodeUpdated {
+    const PALLET: &'static str = "System";
+    const EVENT: &'static str = "CodeUpdated";
+}
+
+// Parachains inherent. Only the validator indices of the signed availability
+// bitfields are needed, which tell which para validators were online.
+
+#[derive(Debug, DecodeAsType)]
+#[decode_as_type(crate_path = "subxt::ext::scale_decode")]
+pub struct Enter {
+    pub data: InherentData,
+}
+
+impl StaticExtrinsic for Enter {
+    const PALLET: &'static str = "ParaInherent";
+    const CALL: &'static str = "enter";
+}
+
+#[derive(Debug, DecodeAsType)]
+#[decode_as_type(crate_path = "subxt::ext::scale_decode")]
+pub struct InherentData {
+    pub bitfields: Vec<AvailabilityBitfield>,
+}
+
+#[derive(Debug, DecodeAsType)]
+#[decode_as_type(crate_path = "subxt::ext::scale_decode")]
+pub struct AvailabilityBitfield {
+    pub validator_index: u32,
+}
+
+// BABE pre-runtime digest. Only the authority index is needed, which is the
+// first field of every variant, so the remaining bytes are left undecoded.
+
+#[derive(Debug, Decode)]
+pub struct AuthorityPreDigest {
+    pub authority_index: u32,
+}
+
+#[derive(Debug, Decode)]
+pub enum PreDigest {
+    #[codec(index = 1)]
+    Primary(AuthorityPreDigest),
+    #[codec(index = 2)]
+    SecondaryPlain(AuthorityPreDigest),
+    #[codec(index = 3)]
+    SecondaryVRF(AuthorityPreDigest),
+}
+
+impl PreDigest {
+    pub fn authority_index(&self) -> u32 {
+        match self {
+            Self::Primary(e) => e.authority_index,
+            Self::SecondaryPlain(e) => e.authority_index,
+            Self::SecondaryVRF(e) => e.authority_index,
+        }
+    }
+}
+
+/// Flatten a dynamic value made of fixed size byte arrays (e.g. session keys or
+/// identity raw data) back into its SCALE encoded bytes
+pub fn value_to_bytes<T>(value: &subxt::ext::scale_value::Value<T>) -> Vec<u8> {
+    match &value.value {
+        ValueDef::Primitive(Primitive::U128(n)) => vec![*n as u8],
+        ValueDef::Composite(composite) => {
+            composite.values().flat_map(|v| value_to_bytes(v)).collect()
+        }
+        ValueDef::Variant(variant) => variant
+            .values
+            .values()
+            .flat_map(|v| value_to_bytes(v))
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// Returns the first unsigned integer found in a dynamic value (e.g. the
+/// validator index of a disabled validator)
+pub fn value_to_u32<T>(value: &subxt::ext::scale_value::Value<T>) -> Option<u32> {
+    match &value.value {
+        ValueDef::Primitive(Primitive::U128(n)) => (*n).try_into().ok(),
+        ValueDef::Composite(composite) => {
+            composite.values().next().and_then(value_to_u32)
+        }
+        _ => None,
+    }
+}
+
+/// Convert the fields of an event, decoded against the runtime metadata, into JSON.
+/// Named fields are kept as an object and unnamed fields as an array.
+pub fn fields_to_json(fields: &Composite<u32>, types: &PortableRegistry) -> JsonValue {
+    match fields {
+        Composite::Named(values) => JsonValue::Object(
+            values
+                .iter()
+                .map(|(name, v)| (name.to_string(), value_to_json(v, types)))
+                .collect::<Map<_, _>>(),
+        ),
+        Composite::Unnamed(values) => {
+            JsonValue::Array(values.iter().map(|v| value_to_json(v, types)).collect())
+        }
+    }
+}
+
+/// Convert a dynamic value, decoded against the runtime metadata, into JSON.
+/// Account ids are shown in the chain SS58 format, byte arrays in hex (0x..),
+/// newtypes (e.g. Perbill) are unwrapped and options are shown as the value or null.
+pub fn value_to_json(
+    value: &subxt::ext::scale_value::Value<u32>,
+    types: &PortableRegistry,
+) -> JsonValue {
+    let ty = types.resolve(value.context);
+    match &value.value {
+        ValueDef::Primitive(primitive) => primitive_to_json(primitive),
+        ValueDef::Composite(composite) => {
+            if type_ident(ty) == Some("AccountId32") {
+                if let Ok(account) = TryInto::<[u8; 32]>::try_into(value_to_bytes(value))
+                {
+                    return json!(sp_runtime::AccountId32::new(account).to_string());
+                }
+            }
+            match ty.map(|ty| &ty.type_def) {
+                Some(TypeDef::Sequence(_)) | Some(TypeDef::Array(_))
+                    if is_bytes(ty, types) =>
+                {
+                    json!(format!("0x{}", hex::encode(value_to_bytes(value))))
+                }
+                Some(TypeDef::Composite(_)) if composite.len() == 1 => match composite {
+                    Composite::Named(_) => fields_to_json(composite, types),
+                    Composite::Unnamed(values) => value_to_json(&values[0], types),
+                },
+                _ => fields_to_json(composite, types),
+            }
+        }
+        ValueDef::Variant(variant) => {
+            if type_ident(ty) == Some("Option") {
+                return match variant.values.values().next() {
+                    Some(v) => value_to_json(v, types),
+                    None => JsonValue::Null,
+                };
+            }
+            match &variant.values {
+                values if values.is_empty() => json!(variant.name),
+                Composite::Unnamed(values) if values.len() == 1 => {
+                    json!({ variant.name.to_string(): value_to_json(&values[0], types) })
+                }
+                values => {
+                    json!({ variant.name.to_string(): fields_to_json(values, types) })
+                }
+            }
+        }
+        ValueDef::BitSequence(bits) => json!(bits
+            .iter()
+            .map(|bit| if bit { '1' } else { '0' })
+            .collect::<String>()),
+    }
+}
+
+fn primitive_to_json(primitive: &Primitive) -> JsonValue {
+    match primitive {
+        Primitive::Bool(b) => json!(b),
+        Primitive::Char(c) => json!(c.to_string()),
+        Primitive::String(s) => json!(s),
+        // NOTE: numbers that do not fit in 64 bits are kept as strings to not lose precision
+        Primitive::U128(n) => match u64::try_from(*n) {
+            Ok(n) => json!(n),
+            Err(_) => json!(n.to_string()),
+        },
+        Primitive::I128(n) => match i64::try_from(*n) {
+            Ok(n) => json!(n),
+            Err(_) => json!(n.to_string()),
+        },
+        Primitive::U256(bytes) | Primitive::I256(bytes) => {
+            json!(format!("0x{}", hex::encode(bytes)))
+        }
+    }
+}
+
+/// Returns the last segment of the type path (e.g. AccountId32)
+fn type_ident(ty: Option<&Type<PortableForm>>) -> Option<&str> {
+    ty.and_then(|ty| ty.path.segments.last())
+        .map(|s| s.as_str())
+}
+
+/// Returns true if the type is a sequence or an array of u8
+fn is_bytes(ty: Option<&Type<PortableForm>>, types: &PortableRegistry) -> bool {
+    let param = match ty.map(|ty| &ty.type_def) {
+        Some(TypeDef::Sequence(seq)) => seq.type_param.id,
+        Some(TypeDef::Array(arr)) => arr.type_param.id,
+        _ => return false,
+    };
+    matches!(
+        types.resolve(param).map(|ty| &ty.type_def),
+        Some(TypeDef::Primitive(TypeDefPrimitive::U8))
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codec::Encode;
+    use subxt::ext::scale_value::Value;
+
+    #[test]
+    fn it_flattens_values_into_bytes() {
+        let keys = Value::unnamed_composite(vec![
+            Value::from_bytes([1u8, 2, 3]),
+            Value::from_bytes([4u8, 5]),
+        ]);
+        assert_eq!(value_to_bytes(&keys), vec![1, 2, 3, 4, 5]);
+
+        let data = Value::unnamed_variant("Raw5", vec![Value::from_bytes(b"scout")]);
+        assert_eq!(value_to_bytes(&data), b"scout".to_vec());
+    }
+
+    #[test]
+    fn it_decodes_authority_index_from_pre_digest() {
+        // SecondaryPlain { authority_index: 42, slot: 7 }
+        let data = (2u8, 42u32, 7u64).encode();
+        let pre = PreDigest::decode(&mut &data[..]).unwrap();
+        assert_eq!(pre.authority_index(), 42);
+    }
+
+    #[test]
+    fn it_converts_values_into_json() {
+        use scale_info::{MetaType, Registry};
+
+        let mut registry = Registry::new();
+        let id = registry
+            .register_type(&MetaType::new::<(
+                sp_runtime::AccountId32,
+                Vec<u8>,
+                Option<u32>,
+                u128,
+                Vec<u16>,
+            )>())
+            .id;
+        let types: PortableRegistry = registry.into();
+
+        let account = sp_runtime::AccountId32::new([1u8; 32]);
+        let data = (
+            account.clone(),
+            vec![0xcau8, 0xfe],
+            None::<u32>,
+            u128::MAX,
+            vec![1u16, 2],
+        )
+            .encode();
+        let value =
+            subxt::ext::scale_value::scale::decode_as_type(&mut &data[..], id, &types)
+                .unwrap();
+        assert_eq!(
+            value_to_json(&value, &types),
+            json!([
+                account.to_string(),
+                "0xcafe",
+                null,
+                u128::MAX.to_string(),
+                [1, 2]
+            ])
+        );
+    }
+
+    #[test]
+    fn it_finds_validator_index_in_values() {
+        assert_eq!(value_to_u32(&Value::u128(7)), Some(7));
+        let disabled = Value::unnamed_composite(vec![Value::u128(3), Value::u128(1_000)]);
+        assert_eq!(value_to_u32(&disabled), Some(3));
+        assert_eq!(value_to_u32(&Value::bool(true)), None);
+    }
+}
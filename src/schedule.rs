@@ -0,0 +1,203 @@
+// The MIT License (MIT)
+// Copyright © 2021 Aukbit Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+// Schedule of the hook executed periodically, independent of chain events,
+// either on a fixed interval or on a cron expression.
+//
+// Cron expressions have 5 fields, evaluated in UTC:
+// `<minute> <hour> <day of month> <month> <day of week>`, each field being `*`,
+// a number, a range (e.g. `1-5`), a step (e.g. `*/15` or `0-30/10`) or a list
+// of them (e.g. `0,30`). Day of week goes from 0 (Sunday) to 6 (Saturday).
+//
+use chrono::{DateTime, Datelike, Duration, Timelike, Utc};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Schedule {
+    Interval(u64),
+    Cron(Cron),
+}
+
+impl Schedule {
+    /// Returns the next time the schedule is due strictly after the given time
+    pub fn next_after(&self, t: DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            Schedule::Interval(seconds) => {
+                t + Duration::seconds(std::cmp::max(1, *seconds) as i64)
+            }
+            Schedule::Cron(cron) => cron.next_after(t),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cron {
+    minutes: Vec<bool>,
+    hours: Vec<bool>,
+    days: Vec<bool>,
+    months: Vec<bool>,
+    weekdays: Vec<bool>,
+    // NOTE: as in standard cron, if both day of month and day of week are restricted
+    // a day matches if any of them matches
+    any_day: bool,
+}
+
+impl FromStr for Cron {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = s.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(format!(
+                "invalid cron expression '{}', expected 5 fields (minute hour day month weekday)",
+                s
+            ));
+        }
+        let weekdays = parse_field(fields[4], 0, 7)?;
+        Ok(Cron {
+            minutes: parse_field(fields[0], 0, 59)?,
+            hours: parse_field(fields[1], 0, 23)?,
+            days: parse_field(fields[2], 1, 31)?,
+            months: parse_field(fields[3], 1, 12)?,
+            // NOTE: both 0 and 7 are Sunday
+            weekdays: (0..7)
+                .map(|d| weekdays[d] || (d == 0 && weekdays[7]))
+                .collect(),
+            any_day: !fields[2].starts_with('*') && !fields[4].starts_with('*'),
+        })
+    }
+}
+
+impl Cron {
+    fn is_day(&self, t: DateTime<Utc>) -> bool {
+        let day = self.days[t.day() as usize];
+        let weekday = self.weekdays[t.weekday().num_days_from_sunday() as usize];
+        self.months[t.month() as usize]
+            && if self.any_day {
+                day || weekday
+            } else {
+                day && weekday
+            }
+    }
+
+    /// Returns the next minute matching the expression strictly after the given time
+    pub fn next_after(&self, t: DateTime<Utc>) -> DateTime<Utc> {
+        let mut next = t
+            .with_second(0)
+            .and_then(|t| t.with_nanosecond(0))
+            .unwrap_or(t)
+            + Duration::minutes(1);
+        // NOTE: expressions that never match (e.g. 30th of February) are retried after 4 years
+        let limit = next + Duration::days(4 * 366);
+        while next < limit {
+            if !self.is_day(next) {
+                next = next
+                    .with_hour(0)
+                    .and_then(|t| t.with_minute(0))
+                    .unwrap_or(next)
+                    + Duration::days(1);
+            } else if !self.hours[next.hour() as usize] {
+                next = next.with_minute(0).unwrap_or(next) + Duration::hours(1);
+            } else if !self.minutes[next.minute() as usize] {
+                next += Duration::minutes(1);
+            } else {
+                return next;
+            }
+        }
+        limit
+    }
+}
+
+/// Returns the values of a cron field as flags, indexed by value from 0 to max
+fn parse_field(field: &str, min: usize, max: usize) -> Result<Vec<bool>, String> {
+    let invalid = || format!("invalid cron field '{}'", field);
+    let mut flags = vec![false; max + 1];
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<usize>().map_err(|_| invalid())?),
+            None => (part, 1),
+        };
+        let (from, to) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((from, to)) => (
+                    from.parse::<usize>().map_err(|_| invalid())?,
+                    to.parse::<usize>().map_err(|_| invalid())?,
+                ),
+                None => {
+                    let value = range.parse::<usize>().map_err(|_| invalid())?;
+                    // NOTE: a single value with a step (e.g. 5/15) runs from that value to the max
+                    (value, if part.contains('/') { max } else { value })
+                }
+            },
+        };
+        if step == 0 || from < min || to > max || from > to {
+            return Err(invalid());
+        }
+        for value in (from..=to).step_by(step) {
+            flags[value] = true;
+        }
+    }
+    Ok(flags)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn it_finds_next_cron_time() {
+        let t = Utc.with_ymd_and_hms(2024, 3, 1, 10, 7, 30).unwrap();
+
+        let cron: Cron = "*/15 * * * *".parse().unwrap();
+        assert_eq!(
+            cron.next_after(t),
+            Utc.with_ymd_and_hms(2024, 3, 1, 10, 15, 0).unwrap()
+        );
+
+        // Weekdays at 09:30, 1st of March 2024 is a Friday
+        let cron: Cron = "30 9 * * 1-5".parse().unwrap();
+        assert_eq!(
+            cron.next_after(t),
+            Utc.with_ymd_and_hms(2024, 3, 4, 9, 30, 0).unwrap()
+        );
+
+        let cron: Cron = "0 0 29 2 *".parse().unwrap();
+        assert_eq!(
+            cron.next_after(t),
+            Utc.with_ymd_and_hms(2028, 2, 29, 0, 0, 0).unwrap()
+        );
+
+        assert!("* * *".parse::<Cron>().is_err());
+        assert!("60 * * * *".parse::<Cron>().is_err());
+        assert!("*/0 * * * *".parse::<Cron>().is_err());
+    }
+
+    #[test]
+    fn it_schedules_on_interval() {
+        let t = Utc.with_ymd_and_hms(2024, 3, 1, 10, 7, 30).unwrap();
+        assert_eq!(
+            Schedule::Interval(300).next_after(t),
+            Utc.with_ymd_and_hms(2024, 3, 1, 10, 12, 30).unwrap()
+        );
+    }
+}
@@ -20,46 +20,62 @@
 // SOFTWARE.
 
 use crate::config::{Config, CONFIG};
+use crate::discord::Discord;
+use crate::email::Email;
 use crate::errors::ScoutyError;
 use crate::hooks::{
     Hook, HOOK_INIT, HOOK_NEW_ERA, HOOK_NEW_SESSION, HOOK_REFERENDA_SUBMITTED,
-    HOOK_VALIDATOR_CHILLED, HOOK_VALIDATOR_OFFLINE, HOOK_VALIDATOR_SLASHED,
-    HOOK_VALIDATOR_STARTS_ACTIVE_NEXT_ERA, HOOK_VALIDATOR_STARTS_INACTIVE_NEXT_ERA,
+    HOOK_RUNTIME_UPGRADED, HOOK_VALIDATOR_CHILLED, HOOK_VALIDATOR_OFFLINE,
+    HOOK_VALIDATOR_SLASHED, HOOK_VALIDATOR_STARTS_ACTIVE_NEXT_ERA,
+    HOOK_VALIDATOR_STARTS_INACTIVE_NEXT_ERA,
 };
+use crate::incidents::{Condition, Incidents};
 use crate::matrix::Matrix;
+use crate::notifier::Notifier;
+use crate::report::{RawData, Report};
 use crate::runtimes::{
-    kusama,
-    polkadot,
-    support::{ChainPrefix, ChainTokenSymbol, SupportedRuntime},
-    // westend,
+    generic::{self, Checkpoint},
+    support::{ChainPrefix, ChainTokenSymbol, RuntimeFeatures, SupportedRuntime},
 };
+use crate::slack::Slack;
+use crate::telegram::Telegram;
 
 use async_std::task;
 use log::{error, info, warn};
-use std::{convert::TryInto, result::Result, str::FromStr, thread, time};
+use std::{convert::TryInto, result::Result, str::FromStr, sync::RwLock, thread, time};
 use subxt::{
     backend::{
         legacy::{rpc_methods::StorageKey, LegacyRpcMethods},
         rpc::RpcClient,
     },
-    ext::sp_core::crypto,
+    ext::sp_core::{crypto, H256},
+    storage::Storage,
     utils::{validate_url_is_secure, AccountId32},
     OnlineClient, PolkadotConfig,
 };
 
+/// SS58 prefix of generic substrate chains
+const DEFAULT_SS58_PREFIX: ChainPrefix = 42;
+
+/// Maximum number of blocks finality may lag behind the best block on a healthy node
+const MAX_FINALITY_LAG: u32 = 30;
+
+/// Maximum number of seconds to wait for a new finalized block before switching endpoint
+pub const FINALIZED_BLOCK_TIMEOUT: u64 = 120;
+
 pub async fn _create_substrate_node_client(
     config: Config,
 ) -> Result<OnlineClient<PolkadotConfig>, subxt::Error> {
-    OnlineClient::<PolkadotConfig>::from_url(config.substrate_ws_url).await
+    OnlineClient::<PolkadotConfig>::from_url(&config.substrate_ws_urls()[0]).await
 }
 
-pub async fn create_substrate_rpc_client_from_config(
-    config: Config,
+pub async fn create_substrate_rpc_client_from_url(
+    url: &str,
 ) -> Result<RpcClient, subxt::Error> {
-    if let Err(_) = validate_url_is_secure(config.substrate_ws_url.as_ref()) {
-        warn!("Insecure URL provided: {}", config.substrate_ws_url);
+    if validate_url_is_secure(url).is_err() {
+        warn!("Insecure URL provided: {}", url);
     };
-    RpcClient::from_insecure_url(config.substrate_ws_url).await
+    RpcClient::from_insecure_url(url).await
 }
 
 pub async fn create_substrate_client_from_rpc_client(
@@ -68,84 +84,157 @@ pub async fn create_substrate_client_from_rpc_client(
     OnlineClient::<PolkadotConfig>::from_rpc_client(rpc_client).await
 }
 
+/// Returns the chain token symbol, or the first one on chains with multiple tokens
+fn token_symbol(value: Option<&serde_json::Value>) -> ChainTokenSymbol {
+    match value {
+        Some(serde_json::Value::String(token_symbol)) => token_symbol.to_string(),
+        Some(serde_json::Value::Array(token_symbols)) => token_symbols
+            .first()
+            .and_then(|token_symbol| token_symbol.as_str())
+            .unwrap_or_default()
+            .to_string(),
+        _ => String::new(),
+    }
+}
+
+/// Verify that the node is synced and that finality is not lagging behind
+pub async fn check_substrate_node_health(
+    rpc: &LegacyRpcMethods<PolkadotConfig>,
+) -> Result<(), ScoutyError> {
+    let health = rpc.system_health().await?;
+    if health.is_syncing {
+        return Err(ScoutyError::EndpointUnhealthy("node is syncing".into()));
+    }
+    if health.should_have_peers && health.peers == 0 {
+        return Err(ScoutyError::EndpointUnhealthy("node has no peers".into()));
+    }
+
+    let finalized_hash = rpc.chain_get_finalized_head().await?;
+    let finalized = rpc.chain_get_header(Some(finalized_hash)).await?;
+    let best = rpc.chain_get_header(None).await?;
+    if let (Some(finalized), Some(best)) = (finalized, best) {
+        let lag = best.number.saturating_sub(finalized.number);
+        if lag > MAX_FINALITY_LAG {
+            return Err(ScoutyError::EndpointUnhealthy(format!(
+                "finality is lagging {} blocks behind",
+                lag
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Returns the configured endpoints in the order they are tried, with the
+/// `unhealthy` one moved last so that it is only used if no other is healthy
+fn endpoints_by_priority(urls: &[String], unhealthy: Option<&str>) -> Vec<String> {
+    let (mut endpoints, unhealthy): (Vec<String>, Vec<String>) = urls
+        .iter()
+        .cloned()
+        .partition(|url| Some(url.as_str()) != unhealthy);
+    endpoints.extend(unhealthy);
+    endpoints
+}
+
+/// Try each configured endpoint in order, the `unhealthy` one last, and connect
+/// to the first healthy node
 pub async fn create_or_await_substrate_node_client(
     config: Config,
+    unhealthy: Option<String>,
 ) -> (
     OnlineClient<PolkadotConfig>,
     LegacyRpcMethods<PolkadotConfig>,
     SupportedRuntime,
+    String,
 ) {
+    let urls = endpoints_by_priority(&config.substrate_ws_urls(), unhealthy.as_deref());
     loop {
-        match create_substrate_rpc_client_from_config(config.clone()).await {
-            Ok(rpc_client) => {
-                let rpc = LegacyRpcMethods::<PolkadotConfig>::new(rpc_client.clone());
-                let chain = rpc.system_chain().await.unwrap_or_default();
-                let name = rpc.system_name().await.unwrap_or_default();
-                let version = rpc.system_version().await.unwrap_or_default();
-                let properties = rpc.system_properties().await.unwrap_or_default();
-
-                // Display SS58 addresses based on the connected chain
-                let chain_prefix: ChainPrefix =
-                    if let Some(ss58_format) = properties.get("ss58Format") {
-                        ss58_format.as_u64().unwrap_or_default().try_into().unwrap()
-                    } else {
-                        0
-                    };
-
-                crypto::set_default_ss58_version(crypto::Ss58AddressFormat::custom(
-                    chain_prefix,
-                ));
-
-                let chain_token_symbol: ChainTokenSymbol =
-                    if let Some(token_symbol) = properties.get("tokenSymbol") {
-                        use serde_json::Value::String;
-                        match token_symbol {
-                            String(token_symbol) => token_symbol.to_string(),
-                            _ => unreachable!("Token symbol with wrong type"),
-                        }
-                    } else {
-                        String::from("")
-                    };
-
-                info!(
-                    "Connected to {} network using {} * Substrate node {} v{}",
-                    chain, config.substrate_ws_url, name, version
-                );
+        for url in urls.iter() {
+            match create_substrate_rpc_client_from_url(url).await {
+                Ok(rpc_client) => {
+                    let rpc = LegacyRpcMethods::<PolkadotConfig>::new(rpc_client.clone());
 
-                match create_substrate_client_from_rpc_client(rpc_client.clone()).await {
-                    Ok(client) => {
-                        break (client, rpc, SupportedRuntime::from(chain_token_symbol));
+                    if let Err(e) = check_substrate_node_health(&rpc).await {
+                        warn!("Skipping {}: {}", url, e);
+                        continue;
                     }
-                    Err(e) => {
-                        error!("{}", e);
-                        info!(
-                            "Awaiting for connection using {}",
-                            config.substrate_ws_url
-                        );
-                        thread::sleep(time::Duration::from_secs(6));
+
+                    let chain = rpc.system_chain().await.unwrap_or_default();
+                    let name = rpc.system_name().await.unwrap_or_default();
+                    let version = rpc.system_version().await.unwrap_or_default();
+                    let properties = rpc.system_properties().await.unwrap_or_default();
+
+                    // Display SS58 addresses based on the connected chain
+                    // NOTE: chains without a valid ss58 format fall back to the generic substrate prefix
+                    let chain_prefix: ChainPrefix = properties
+                        .get("ss58Format")
+                        .and_then(|ss58_format| ss58_format.as_u64())
+                        .and_then(|ss58_format| ss58_format.try_into().ok())
+                        .unwrap_or(DEFAULT_SS58_PREFIX);
+
+                    crypto::set_default_ss58_version(crypto::Ss58AddressFormat::custom(
+                        chain_prefix,
+                    ));
+
+                    let chain_token_symbol: ChainTokenSymbol =
+                        token_symbol(properties.get("tokenSymbol"));
+
+                    info!(
+                        "Connected to {} network using {} * Substrate node {} v{}",
+                        chain, url, name, version
+                    );
+
+                    match create_substrate_client_from_rpc_client(rpc_client.clone())
+                        .await
+                    {
+                        Ok(client) => {
+                            return (
+                                client,
+                                rpc,
+                                SupportedRuntime::from(chain_token_symbol),
+                                url.to_string(),
+                            );
+                        }
+                        Err(e) => {
+                            error!("{}", e);
+                        }
                     }
                 }
-            }
-            Err(e) => {
-                error!("{}", e);
-                info!("Awaiting for connection using {}", config.substrate_ws_url);
-                thread::sleep(time::Duration::from_secs(6));
+                Err(e) => {
+                    error!("{}", e);
+                }
             }
         }
+        info!("Awaiting for connection using {}", urls.join(", "));
+        thread::sleep(time::Duration::from_secs(6));
     }
 }
 
 pub struct Scouty {
     runtime: SupportedRuntime,
+    features: RuntimeFeatures,
+    eras_per_day: u32,
     client: OnlineClient<PolkadotConfig>,
     rpc: LegacyRpcMethods<PolkadotConfig>,
-    matrix: Matrix,
+    notifiers: Vec<Box<dyn Notifier>>,
+    incidents: Incidents,
+    endpoint: String,
+    block_hash: RwLock<Option<H256>>,
 }
 
 impl Scouty {
-    async fn new() -> Scouty {
-        let (client, rpc, runtime) =
-            create_or_await_substrate_node_client(CONFIG.clone()).await;
+    async fn new(unhealthy_endpoint: Option<String>) -> Scouty {
+        let (client, rpc, runtime, endpoint) =
+            create_or_await_substrate_node_client(CONFIG.clone(), unhealthy_endpoint)
+                .await;
+
+        // Discover runtime features from the live metadata
+        let metadata = client.metadata();
+        let features = RuntimeFeatures::from(&metadata);
+        info!("{} runtime pallets available: {}", runtime, features);
+        let eras_per_day = runtime
+            .eras_per_day()
+            .or_else(|| RuntimeFeatures::eras_per_day(&metadata))
+            .unwrap_or(1);
 
         // Initialize matrix client
         let mut matrix: Matrix = Matrix::new();
@@ -153,15 +242,55 @@ impl Scouty {
             error!("{}", e);
             Default::default()
         });
+        let mut notifiers: Vec<Box<dyn Notifier>> = vec![Box::new(matrix)];
+
+        // Initialize telegram client
+        if let Some(telegram) = Telegram::new() {
+            notifiers.push(Box::new(telegram));
+        }
+
+        // Initialize discord client
+        if let Some(discord) = Discord::new() {
+            notifiers.push(Box::new(discord));
+        }
+
+        // Initialize slack client
+        if let Some(slack) = Slack::new() {
+            notifiers.push(Box::new(slack));
+        }
+
+        // Initialize email client
+        if let Some(email) = Email::new() {
+            notifiers.push(Box::new(email));
+        }
 
         Scouty {
             runtime,
+            features,
+            eras_per_day,
             client,
             rpc,
-            matrix,
+            notifiers,
+            incidents: Incidents::new(),
+            endpoint,
+            block_hash: RwLock::new(None),
         }
     }
 
+    pub fn runtime(&self) -> SupportedRuntime {
+        self.runtime
+    }
+
+    /// Returns the pallets available in the connected runtime
+    pub fn features(&self) -> RuntimeFeatures {
+        self.features
+    }
+
+    /// Returns the number of eras in a day
+    pub fn eras_per_day(&self) -> u32 {
+        self.eras_per_day
+    }
+
     pub fn client(&self) -> &OnlineClient<PolkadotConfig> {
         &self.client
     }
@@ -170,19 +299,98 @@ impl Scouty {
         &self.rpc
     }
 
-    /// Returns the matrix configuration
-    pub fn matrix(&self) -> &Matrix {
-        &self.matrix
+    /// Returns the RPC endpoint currently in use
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// Sets the block from which storage is read, or the latest block if none
+    pub fn set_block_hash(&self, block_hash: Option<H256>) {
+        *self.block_hash.write().unwrap() = block_hash;
+    }
+
+    /// Returns storage at the block being processed, or at the latest block
+    pub async fn storage(
+        &self,
+    ) -> Result<Storage<PolkadotConfig, OnlineClient<PolkadotConfig>>, ScoutyError> {
+        let block_hash = *self.block_hash.read().unwrap();
+        match block_hash {
+            Some(block_hash) => Ok(self.client.storage().at(block_hash)),
+            None => Ok(self.client.storage().at_latest().await?),
+        }
     }
 
+    /// Send a message to every notifier, a notifier failing does not prevent
+    /// the next ones from sending it, the first error is returned
     pub async fn send_message(
         &self,
         message: &str,
         formatted_message: &str,
     ) -> Result<(), ScoutyError> {
-        self.matrix()
-            .send_message(message, formatted_message)
-            .await?;
+        let mut result = Ok(());
+        for notifier in self.notifiers.iter() {
+            if let Err(e) = notifier.send_message(message, formatted_message).await {
+                warn!("{} message not sent: {}", notifier.name(), e);
+                result = result.and(Err(e));
+            }
+        }
+        result
+    }
+
+    /// Send a report to every notifier, a notifier failing is only logged so that
+    /// the next ones still send it and monitoring carries on
+    async fn send_report(&self, report: &Report, matrix_user: Option<&str>) {
+        for notifier in self.notifiers.iter() {
+            if let Err(e) = notifier.send_report(report, matrix_user).await {
+                warn!("{} report not sent: {}", notifier.name(), e);
+            }
+        }
+    }
+
+    /// Resolve the incidents of a condition observed as cleared for the stashes,
+    /// for observations that are not part of a notification. Failures are only logged.
+    pub async fn clear_incidents(&self, condition: Condition, stashes: &[AccountId32]) {
+        if stashes.is_empty() || !self.incidents.is_tracked(condition) {
+            return;
+        }
+        match self.rpc.system_chain().await {
+            Ok(chain_name) => self.incidents.clear(&chain_name, stashes, condition).await,
+            Err(e) => warn!("Incidents not resolved: {}", e),
+        }
+    }
+
+    /// Send a report to every notifier and matrix user to which the data is routed,
+    /// unless every hook in the report asked for the notification to be suppressed.
+    /// Notifiers failing to deliver the report never return an error.
+    pub async fn notify(&self, data: RawData) -> Result<(), ScoutyError> {
+        let config = CONFIG.clone();
+        // NOTE: while testing a hook the raw output and the report are printed instead
+        if config.hooks_test.is_some() {
+            for hook in data.hooks() {
+                println!(
+                    "Exit code: {:?} ({:.2}s)",
+                    hook.exit_code,
+                    hook.duration.as_secs_f64()
+                );
+                println!("Stdout:\n{}", String::from_utf8_lossy(&hook.stdout));
+                if !hook.stderr.is_empty() {
+                    println!("Stderr:\n{}", String::from_utf8_lossy(&hook.stderr));
+                }
+            }
+        }
+        self.incidents.track(&data).await;
+        for (matrix_user, data) in data.route() {
+            let report = Report::from(data);
+            if report.is_suppressed() {
+                info!("Notification suppressed by hooks");
+                continue;
+            }
+            if config.hooks_test.is_some() {
+                println!("Report:\n{}", report.message());
+                continue;
+            }
+            self.send_report(&report, matrix_user.as_deref()).await;
+        }
         Ok(())
     }
 
@@ -191,7 +399,68 @@ impl Scouty {
         spawn_and_restart_subscription_on_error();
     }
 
-    async fn subscribe_on_chain_events(&self) -> Result<(), ScoutyError> {
+    /// Verify that the pallets required by scouty are available in the runtime
+    fn verify_runtime_features(&self) -> Result<(), ScoutyError> {
+        if !self.features.is_supported() {
+            return Err(ScoutyError::Other(format!(
+                "{} runtime is missing the Session or Staking pallet required by scouty",
+                self.runtime
+            )));
+        }
+        Ok(())
+    }
+
+    /// Replay finalized blocks within a range and exit
+    pub fn replay(from: u32, to: u32) {
+        task::block_on(async {
+            let c: Scouty = Scouty::new(None).await;
+            if let Err(e) = c.replay_on_chain_events(from, to).await {
+                error!("{}", e);
+            }
+        });
+    }
+
+    /// Run a single hook against the latest or a historical finalized block and exit
+    pub fn test_hook(hook: &str, at: Option<u32>) {
+        task::block_on(async {
+            let c: Scouty = Scouty::new(None).await;
+            if let Err(e) = c.test_hook_on_chain_data(hook, at).await {
+                error!("{}", e);
+            }
+        });
+    }
+
+    async fn test_hook_on_chain_data(
+        &self,
+        hook: &str,
+        at: Option<u32>,
+    ) -> Result<(), ScoutyError> {
+        self.verify_runtime_features()?;
+
+        generic::init_and_test_hook(self, hook, at).await
+    }
+
+    async fn replay_on_chain_events(
+        &self,
+        from: u32,
+        to: u32,
+    ) -> Result<(), ScoutyError> {
+        if from > to {
+            return Err(ScoutyError::Other(format!(
+                "Invalid block range: #{} is greater than #{}",
+                from, to
+            )));
+        }
+
+        self.verify_runtime_features()?;
+
+        generic::init_and_replay_on_chain_events(self, from, to).await
+    }
+
+    async fn subscribe_on_chain_events(
+        &self,
+        checkpoint: &mut Checkpoint,
+    ) -> Result<(), ScoutyError> {
         let config = CONFIG.clone();
 
         // Verify if hooks scripts are available
@@ -213,37 +482,38 @@ impl Scouty {
             HOOK_REFERENDA_SUBMITTED,
             &config.hook_referenda_submitted_path,
         );
+        Hook::exists(HOOK_RUNTIME_UPGRADED, &config.hook_runtime_upgraded_path);
 
-        match self.runtime {
-            SupportedRuntime::Polkadot => {
-                polkadot::init_and_subscribe_on_chain_events(self).await
-            }
-            SupportedRuntime::Kusama => {
-                kusama::init_and_subscribe_on_chain_events(self).await
-            }
-            // SupportedRuntime::Westend => {
-            //     westend::init_and_subscribe_on_chain_events(self).await
-            // }
-            _ => unreachable!(),
-        }
+        self.verify_runtime_features()?;
+
+        generic::init_and_subscribe_on_chain_events(self, checkpoint).await
     }
 }
 
 fn spawn_and_restart_subscription_on_error() {
     let t = task::spawn(async {
         let config = CONFIG.clone();
+        let mut unhealthy_endpoint: Option<String> = None;
+        // Keep track of the last processed block to catch up on missed blocks after reconnecting
+        let mut checkpoint = Checkpoint::default();
         loop {
-            let c: Scouty = Scouty::new().await;
-            if let Err(e) = c.subscribe_on_chain_events().await {
+            let c: Scouty = Scouty::new(unhealthy_endpoint.take()).await;
+            if let Err(e) = c.subscribe_on_chain_events(&mut checkpoint).await {
                 match e {
                     ScoutyError::SubscriptionFinished => warn!("{}", e),
+                    ScoutyError::EndpointUnhealthy(_) => {
+                        warn!("{} {}, switching endpoint", c.endpoint(), e);
+                        unhealthy_endpoint = Some(c.endpoint().to_string());
+                    }
                     ScoutyError::MatrixError(_) => warn!("Matrix message skipped!"),
                     _ => {
                         error!("{}", e);
                         let message =
                             format!("On hold for {} min!", config.error_interval);
                         let formatted_message = format!("<br/>🚨 An error was raised -> <code>scouty</code> on hold for {} min while rescue is on the way 🚁 🚒 🚑 🚓<br/><br/>", config.error_interval);
-                        c.send_message(&message, &formatted_message).await.unwrap();
+                        if c.send_message(&message, &formatted_message).await.is_err() {
+                            warn!("On hold message skipped!");
+                        }
                         thread::sleep(time::Duration::from_secs(
                             60 * config.error_interval,
                         ));
@@ -267,3 +537,33 @@ pub fn convert_account_id(acc: AccountId32) -> subxt::ext::sp_runtime::AccountId
     subxt::ext::sp_runtime::AccountId32::from_str(&acc.to_string())
         .expect("invalid account id")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn it_tries_the_unhealthy_endpoint_last() {
+        let urls: Vec<String> =
+            vec!["wss://a".into(), "wss://b".into(), "wss://c".into()];
+        assert_eq!(endpoints_by_priority(&urls, None), urls);
+        assert_eq!(
+            endpoints_by_priority(&urls, Some("wss://a")),
+            vec!["wss://b", "wss://c", "wss://a"]
+        );
+        assert_eq!(
+            endpoints_by_priority(&urls[..1], Some("wss://a")),
+            vec!["wss://a"]
+        );
+    }
+
+    #[test]
+    fn it_reads_token_symbol_from_chain_properties() {
+        assert_eq!(token_symbol(Some(&json!("DOT"))), "DOT");
+        assert_eq!(token_symbol(Some(&json!(["ACA", "AUSD"]))), "ACA");
+        assert_eq!(token_symbol(Some(&json!([]))), "");
+        assert_eq!(token_symbol(Some(&json!(12))), "");
+        assert_eq!(token_symbol(None), "");
+    }
+}
@@ -0,0 +1,397 @@
+// The MIT License (MIT)
+// Copyright © 2021 Aukbit Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::config::CONFIG;
+use crate::errors::{ScoutyError, SlackError};
+use crate::hooks::Severity;
+use crate::notifier::{parse_html, split_message, unescape_text, Html, Notifier};
+use crate::report::{Card, Report, Status};
+use async_std::task;
+use async_trait::async_trait;
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::{result::Result, str::FromStr, time::Duration};
+use subxt::utils::AccountId32;
+
+/// Maximum number of characters of a section block text
+const SLACK_MAX_TEXT_LENGTH: usize = 3000;
+
+/// Maximum number of blocks of a message
+const SLACK_MAX_BLOCKS: usize = 50;
+
+/// Maximum number of times a message is retried when rate limited
+const SLACK_MAX_RETRIES: u32 = 3;
+
+/// Slack or Mattermost incoming webhook to which reports are sent, optionally
+/// only the ones with a minimum severity or about particular validator stashes
+#[derive(Clone, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct SlackWebhook {
+    pub url: String,
+    #[serde(default)]
+    pub severity: Severity,
+    #[serde(default)]
+    pub stashes: Vec<String>,
+}
+
+impl SlackWebhook {
+    /// Returns the validator cards of the report to be sent to the webhook, or None if
+    /// the report is not for the webhook. Reports without validators are only sent
+    /// to webhooks not restricted to particular stashes.
+    fn select<'a>(&self, report: &'a Report) -> Option<Vec<&'a Card>> {
+        if report.severity() < self.severity {
+            return None;
+        }
+        if self.stashes.is_empty() {
+            return Some(report.cards().iter().collect());
+        }
+        let stashes: Vec<AccountId32> = self
+            .stashes
+            .iter()
+            .filter_map(|stash| AccountId32::from_str(stash).ok())
+            .collect();
+        let cards: Vec<&Card> = report
+            .cards()
+            .iter()
+            .filter(|card| stashes.contains(&card.stash))
+            .collect();
+        if cards.is_empty() {
+            None
+        } else {
+            Some(cards)
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+struct Text {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    text: String,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Block {
+    Section { text: Text },
+    Divider,
+}
+
+impl Block {
+    fn section(text: String) -> Block {
+        Block::Section {
+            text: Text {
+                kind: "mrkdwn",
+                text,
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Default)]
+struct WebhookMessage {
+    // NOTE: shown in notifications and by Mattermost, which ignores blocks
+    text: String,
+    blocks: Vec<Block>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Slack {
+    client: reqwest::Client,
+    webhooks: Vec<SlackWebhook>,
+}
+
+impl Slack {
+    /// Returns a Slack notifier if any incoming webhook is defined
+    pub fn new() -> Option<Slack> {
+        let config = CONFIG.clone();
+        if config.slack_disabled || config.slack_webhooks.is_empty() {
+            return None;
+        }
+        info!(
+            "Messages will be sent to {} Slack webhook(s)",
+            config.slack_webhooks.len()
+        );
+        Some(Slack {
+            client: reqwest::Client::new(),
+            webhooks: config.slack_webhooks,
+        })
+    }
+
+    /// Send a message to a webhook, waiting as requested by Slack when rate limited
+    async fn dispatch_message(
+        &self,
+        url: &str,
+        message: &WebhookMessage,
+    ) -> Result<(), SlackError> {
+        let mut attempt: u32 = 0;
+        loop {
+            let res = self.client.post(url).json(message).send().await?;
+            let status = res.status();
+
+            match status {
+                _ if status.is_success() => {
+                    debug!("Slack message dispatched");
+                    return Ok(());
+                }
+                reqwest::StatusCode::TOO_MANY_REQUESTS if attempt < SLACK_MAX_RETRIES => {
+                    let retry_after = res
+                        .headers()
+                        .get("retry-after")
+                        .and_then(|v| v.to_str().ok())
+                        .and_then(|v| v.parse::<u64>().ok())
+                        .unwrap_or(5);
+                    warn!(
+                        "Slack rate limited -> Wait {} seconds and try again",
+                        retry_after
+                    );
+                    task::sleep(Duration::from_secs(retry_after)).await;
+                    attempt += 1;
+                }
+                _ => {
+                    let body = res.text().await.unwrap_or_default();
+                    return Err(SlackError::Other(format!("{} ({})", body, status)));
+                }
+            }
+        }
+    }
+
+    /// Send the messages to every webhook selected, a webhook failing does not prevent
+    /// the next ones from getting them
+    async fn dispatch_messages<F>(&self, messages_for: F) -> Result<(), ScoutyError>
+    where
+        F: Fn(&SlackWebhook) -> Option<Vec<WebhookMessage>>,
+    {
+        let mut result = Ok(());
+        // NOTE: webhooks are identified by position, their url is a secret
+        for (i, webhook) in self.webhooks.iter().enumerate() {
+            if let Some(messages) = messages_for(webhook) {
+                for message in messages {
+                    if let Err(e) = self.dispatch_message(&webhook.url, &message).await {
+                        warn!("Slack message not sent to webhook #{}: {}", i + 1, e);
+                        result = Err(e.into());
+                        break;
+                    }
+                }
+            }
+        }
+        result
+    }
+}
+
+#[async_trait]
+impl Notifier for Slack {
+    fn name(&self) -> &'static str {
+        "Slack"
+    }
+
+    /// Send the message to every webhook not restricted by severity or stashes
+    async fn send_message(
+        &self,
+        _message: &str,
+        formatted_message: &str,
+    ) -> Result<(), ScoutyError> {
+        self.dispatch_messages(|webhook| {
+            if webhook.severity > Severity::Info || !webhook.stashes.is_empty() {
+                return None;
+            }
+            Some(build_messages(formatted_message, &[]))
+        })
+        .await
+    }
+
+    /// Send the report to every webhook selected by its severity and stashes,
+    /// with only the validators of the stashes defined for each webhook
+    async fn send_report(
+        &self,
+        report: &Report,
+        _matrix_user: Option<&str>,
+    ) -> Result<(), ScoutyError> {
+        let mut summary = report.summary();
+        summary.dedup_by(|a, b| a.is_empty() && b.is_empty());
+        let summary = summary.join("<br/>");
+        self.dispatch_messages(|webhook| {
+            webhook
+                .select(report)
+                .map(|cards| build_messages(&summary, &cards))
+        })
+        .await
+    }
+}
+
+/// Returns the messages with a section for the summary and for each validator card,
+/// split within Slack limits
+fn build_messages(summary: &str, cards: &[&Card]) -> Vec<WebhookMessage> {
+    let mut blocks: Vec<Block> =
+        split_message(to_slack_mrkdwn(summary).trim(), SLACK_MAX_TEXT_LENGTH)
+            .into_iter()
+            .map(Block::section)
+            .collect();
+
+    for card in cards {
+        let mut text = format!(
+            "{} *<{}|{}>*",
+            status_emoji(card.status),
+            card.url,
+            escape_text(&card.title)
+        );
+        let lines = to_slack_mrkdwn(&card.lines.join("<br/>"));
+        if !lines.trim().is_empty() {
+            text.push('\n');
+            text.push_str(lines.trim());
+        }
+        for (severity, line) in card.alerts.iter() {
+            let severity_desc = match severity {
+                Severity::Critical => "🚨",
+                _ => "⚠️",
+            };
+            text.push_str(&format!("\n‣ {} {}", severity_desc, to_slack_mrkdwn(line)));
+        }
+        blocks.push(Block::Divider);
+        blocks.extend(
+            split_message(&text, SLACK_MAX_TEXT_LENGTH)
+                .into_iter()
+                .map(Block::section),
+        );
+    }
+
+    blocks
+        .chunks(SLACK_MAX_BLOCKS)
+        .map(|blocks| WebhookMessage {
+            text: blocks
+                .iter()
+                .filter_map(|block| match block {
+                    Block::Section { text } => Some(text.text.as_str()),
+                    Block::Divider => None,
+                })
+                .collect::<Vec<&str>>()
+                .join("\n\n"),
+            blocks: blocks.to_vec(),
+        })
+        .collect()
+}
+
+fn status_emoji(status: Status) -> &'static str {
+    match status {
+        Status::Active => "🟢",
+        Status::Inactive => "🔴",
+        Status::Slashed => "🏴‍☠️",
+        Status::Chilled => "🧊",
+        Status::Offline => "⚪",
+    }
+}
+
+/// Escape the characters that Slack uses for its own markup
+fn escape_text(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Translate the HTML of a report into Slack mrkdwn, tags without an
+/// equivalent are removed
+pub fn to_slack_mrkdwn(html: &str) -> String {
+    let mut text = String::new();
+    // NOTE: true for each open link with href, links without href are kept as text
+    let mut links: Vec<bool> = Vec::new();
+    for node in parse_html(html) {
+        let tag = match node {
+            Html::Text(t) => {
+                text.push_str(&escape_text(&unescape_text(t)));
+                continue;
+            }
+            Html::Tag(tag) => tag,
+        };
+        let is_closing = tag.is_closing;
+        match tag.name.as_str() {
+            "br" => text.push('\n'),
+            "b" | "strong" => text.push('*'),
+            "i" | "em" => text.push('_'),
+            "s" | "strike" | "del" => text.push('~'),
+            "code" => text.push('`'),
+            "pre" => text.push_str("```"),
+            "a" if is_closing && links.pop().unwrap_or(false) => text.push('>'),
+            "a" if is_closing => (),
+            "a" => match tag.href() {
+                Some(href) => {
+                    links.push(true);
+                    text.push_str(&format!("<{}|", unescape_text(href)));
+                }
+                None => links.push(false),
+            },
+            _ => (),
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::{Network, Validator};
+
+    #[test]
+    fn it_translates_report_html_to_slack_mrkdwn() {
+        assert_eq!(
+            to_slack_mrkdwn(
+                "🤖 <code>scouty</code><br/><br/>🟢 <b><a href=\"https://polkadot.subscan.io/validator/1\">Validator</a></b><br/>‣ cpu &lt; 80% & <i>ok</i> <a>fine</a>"
+            ),
+            "🤖 `scouty`\n\n🟢 *<https://polkadot.subscan.io/validator/1|Validator>*\n‣ cpu &lt; 80% &amp; _ok_ fine"
+        );
+    }
+
+    #[test]
+    fn it_selects_webhooks_by_severity_and_stashes() {
+        let alice = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+        let bob = "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty";
+        let mut report = Report::new();
+        for stash in [alice, bob] {
+            report.add_validator(
+                &Validator::new(AccountId32::from_str(stash).unwrap()),
+                &Network::default(),
+            );
+            report.close_validator();
+        }
+        let webhook = |severity: Severity, stashes: Vec<&str>| SlackWebhook {
+            url: "https://hooks.slack.com/services/T0/B0/X".to_string(),
+            severity,
+            stashes: stashes.into_iter().map(String::from).collect(),
+        };
+
+        assert_eq!(
+            webhook(Severity::Info, vec![])
+                .select(&report)
+                .map(|c| c.len()),
+            Some(2)
+        );
+        assert_eq!(
+            webhook(Severity::Info, vec![bob])
+                .select(&report)
+                .map(|c| c.len()),
+            Some(1)
+        );
+        assert!(webhook(Severity::Warning, vec![]).select(&report).is_none());
+
+        let messages = build_messages("⛓️ <b>Polkadot</b>", &[&report.cards()[0]]);
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].blocks.len(), 3);
+    }
+}
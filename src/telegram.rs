@@ -0,0 +1,278 @@
+// The MIT License (MIT)
+// Copyright © 2021 Aukbit Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::config::CONFIG;
+use crate::errors::{ScoutyError, TelegramError};
+use crate::notifier::{
+    parse_html, split_message, strip_tags, unescape_text, Html, Notifier,
+};
+use async_std::task;
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use log::{debug, info, warn};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{result::Result, time::Duration};
+
+const TELEGRAM_URL: &str = "https://api.telegram.org";
+
+/// Maximum number of characters of a Telegram message
+const TELEGRAM_MAX_MESSAGE_LENGTH: usize = 4096;
+
+/// Maximum number of times a message is retried when rate limited
+const TELEGRAM_MAX_RETRIES: u32 = 3;
+
+lazy_static! {
+    static ref ENTITY: Regex =
+        Regex::new(r"^&(lt|gt|amp|quot|#[0-9]+|#x[0-9a-fA-F]+);").unwrap();
+}
+
+#[derive(Serialize, Debug)]
+struct SendMessageRequest<'a> {
+    chat_id: &'a str,
+    text: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parse_mode: Option<&'a str>,
+    disable_web_page_preview: bool,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct ResponseParameters {
+    retry_after: Option<u64>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct SendMessageResponse {
+    #[serde(default)]
+    ok: bool,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    parameters: Option<ResponseParameters>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Telegram {
+    client: reqwest::Client,
+    bot_token: String,
+    chat_ids: Vec<String>,
+}
+
+impl Telegram {
+    /// Returns a Telegram notifier if a bot token and chat ids are defined
+    pub fn new() -> Option<Telegram> {
+        let config = CONFIG.clone();
+        if config.telegram_disabled || config.telegram_bot_token.is_empty() {
+            return None;
+        }
+        if config.telegram_chat_ids.is_empty() {
+            warn!("Telegram bot token defined without chat ids, Telegram messages will not be sent");
+            return None;
+        }
+        info!(
+            "Messages will be sent to Telegram chats {}",
+            config.telegram_chat_ids.join(", ")
+        );
+        Some(Telegram {
+            client: reqwest::Client::new(),
+            bot_token: config.telegram_bot_token,
+            chat_ids: config.telegram_chat_ids,
+        })
+    }
+
+    /// Send a message to a chat, waiting as requested by Telegram when rate limited.
+    /// Messages that Telegram fails to parse are sent again as plain text.
+    async fn dispatch_message(
+        &self,
+        chat_id: &str,
+        text: &str,
+    ) -> Result<(), TelegramError> {
+        let plain_text = to_plain_text(text);
+        let mut parse_mode = Some("HTML");
+        let mut attempt: u32 = 0;
+        loop {
+            let req = SendMessageRequest {
+                chat_id,
+                text: if parse_mode.is_some() {
+                    text
+                } else {
+                    &plain_text
+                },
+                parse_mode,
+                disable_web_page_preview: true,
+            };
+            let res = self
+                .client
+                .post(format!(
+                    "{}/bot{}/sendMessage",
+                    TELEGRAM_URL, self.bot_token
+                ))
+                .json(&req)
+                .send()
+                .await?;
+            let status = res.status();
+            let response = res.json::<SendMessageResponse>().await.unwrap_or_default();
+            debug!("response {:?} {:?}", response, status);
+
+            match status {
+                reqwest::StatusCode::OK if response.ok => {
+                    debug!("Telegram message dispatched to chat {}", chat_id);
+                    return Ok(());
+                }
+                reqwest::StatusCode::TOO_MANY_REQUESTS
+                    if attempt < TELEGRAM_MAX_RETRIES =>
+                {
+                    let retry_after =
+                        response.parameters.and_then(|p| p.retry_after).unwrap_or(5);
+                    warn!(
+                        "Telegram {} -> Wait {} seconds and try again",
+                        response.description, retry_after
+                    );
+                    task::sleep(Duration::from_secs(retry_after)).await;
+                    attempt += 1;
+                }
+                reqwest::StatusCode::BAD_REQUEST
+                    if parse_mode.is_some()
+                        && response.description.contains("can't parse entities") =>
+                {
+                    warn!(
+                        "Telegram {} -> Send message as plain text",
+                        response.description
+                    );
+                    parse_mode = None;
+                }
+                _ => {
+                    return Err(TelegramError::Other(format!(
+                        "chat {}: {} ({})",
+                        chat_id, response.description, status
+                    )))
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for Telegram {
+    fn name(&self) -> &'static str {
+        "Telegram"
+    }
+
+    /// Send the message to every chat, a chat failing does not prevent the next ones from getting it
+    async fn send_message(
+        &self,
+        _message: &str,
+        formatted_message: &str,
+    ) -> Result<(), ScoutyError> {
+        let text = to_telegram_html(formatted_message);
+        let mut result = Ok(());
+        for chat_id in self.chat_ids.iter() {
+            for chunk in split_message(&text, TELEGRAM_MAX_MESSAGE_LENGTH) {
+                if let Err(e) = self.dispatch_message(chat_id, &chunk).await {
+                    warn!("Telegram message not sent: {}", e);
+                    result = Err(e.into());
+                    break;
+                }
+            }
+        }
+        result
+    }
+}
+
+/// Translate the HTML subset used in reports into the HTML subset supported by Telegram,
+/// line breaks become new lines and any tag not supported is removed, keeping its content
+pub fn to_telegram_html(html: &str) -> String {
+    let mut text = String::new();
+    // NOTE: links without href are removed together with their closing tag
+    let mut links: Vec<bool> = Vec::new();
+    for node in parse_html(html) {
+        let tag = match node {
+            Html::Text(t) => {
+                text.push_str(&escape_text(t));
+                continue;
+            }
+            Html::Tag(tag) => tag,
+        };
+        let is_closing = tag.is_closing;
+        let name = tag.name.as_str();
+        match name {
+            "br" => text.push('\n'),
+            "p" | "div" | "tr" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6"
+                if is_closing =>
+            {
+                text.push('\n')
+            }
+            "li" if !is_closing => text.push_str("• "),
+            "li" => text.push('\n'),
+            "b" | "strong" | "i" | "em" | "u" | "ins" | "s" | "strike" | "del"
+            | "code" | "pre" | "blockquote" | "tg-spoiler" => {
+                text.push_str(&format!("<{}{}>", if is_closing { "/" } else { "" }, name))
+            }
+            "a" if is_closing && links.pop().unwrap_or(false) => text.push_str("</a>"),
+            "a" if is_closing => (),
+            "a" => match tag.href() {
+                Some(href) => {
+                    links.push(true);
+                    text.push_str(&format!("<a href=\"{}\">", href));
+                }
+                None => links.push(false),
+            },
+            _ => (),
+        }
+    }
+    text
+}
+
+/// Escape characters that are not part of a tag or of an entity supported by Telegram
+fn escape_text(text: &str) -> String {
+    let text = text.replace("&nbsp;", " ");
+    let mut escaped = String::new();
+    for (i, c) in text.char_indices() {
+        match c {
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '&' if !ENTITY.is_match(&text[i..]) => escaped.push_str("&amp;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Remove tags and unescape entities from Telegram HTML
+fn to_plain_text(html: &str) -> String {
+    unescape_text(&strip_tags(html))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_translates_report_html_to_telegram_html() {
+        assert_eq!(
+            to_telegram_html(
+                "🤖 <code>scouty</code><br/><br/>🟢 <b><a href=\"https://polkadot.subscan.io/validator/1\">Validator</a></b><br/>‣ cpu < 80% & <i>ok</i> &amp; <span>fine</span>"
+            ),
+            "🤖 <code>scouty</code>\n\n🟢 <b><a href=\"https://polkadot.subscan.io/validator/1\">Validator</a></b>\n‣ cpu &lt; 80% &amp; <i>ok</i> &amp; fine"
+        );
+        assert_eq!(to_plain_text("<b>1 &lt; 2 &amp;&amp; 3</b>"), "1 < 2 && 3");
+    }
+}